@@ -0,0 +1,80 @@
+use std::io;
+use std::env;
+use std::time::Instant;
+
+////////////////////////////////////////////////////////////
+
+use structs::run_config::Config;
+use structs::run_stats::{RunStats, seconds};
+use simulate::{self, SimConfig};
+use modes;
+use prepare;
+use solve;
+
+/*
+Runs the solver on each dataset with each mode, printing the timings and counters of every run
+as a line of TSV to stdout. Without datasets, reads are simulated (see simulate.rs) and used instead.
+Solutions are written to config.output, which is overwritten by every run.
+*/
+pub struct BenchConfig {
+    pub datasets : Vec<String>,
+    pub simulation : SimConfig,   // used if there are no datasets. its paths are replaced
+    pub modes : Vec<String>,      // as given with -m. already known to parse
+    pub repeats : usize,
+    pub config : Config,          // input and max_errors are set for each run
+}
+
+// the timing columns and the stages of RunStats they sum
+static STAGE_COLUMNS : &'static [(&'static str, &'static str)] = &[
+    ("parsing",         "parsing"),
+    ("suffix_array",    "suffix array"),
+    ("bwt_occ",         "bwt and occ"),
+    ("rev_suffix_array", "reversed suffix array"),
+    ("rev_bwt_occ",     "reversed bwt and occ"),
+    ("search_wall",     "search (wall clock)"),
+    ("generation",      "candidate generation"),
+    ("verification",    "verification"),
+    ("sorting",         "sorting"),
+    ("writing",         "writing"),
+];
+
+pub fn benchmark(mut bench : BenchConfig) -> Result<(), io::Error> {
+    let datasets = if bench.datasets.is_empty() {
+        let dir = env::temp_dir();
+        bench.simulation.reads_path = dir.join("rust_overlaps_benchmark_reads.fasta").to_string_lossy().into_owned();
+        bench.simulation.truth_path = dir.join("rust_overlaps_benchmark_truth.tsv").to_string_lossy().into_owned();
+        let (reads, overlaps) = simulate::simulate(&bench.simulation)?;
+        println!("# simulated {} reads with {} true overlaps at {}", reads, overlaps, bench.simulation.reads_path);
+        vec![bench.simulation.reads_path.clone()]
+    } else {
+        bench.datasets.clone()
+    };
+
+    let columns : Vec<&str> = STAGE_COLUMNS.iter().map(|&(column, _)| column).collect();
+    println!("dataset\tmode\trun\t{}\ttotal\tnodes\tgenerated\tverified\tsolutions", columns.join("\t"));
+    for dataset in datasets.iter() {
+        bench.config.input = dataset.clone();
+        for mode_arg in bench.modes.iter() {
+            for run in 0..bench.repeats {
+                let mode = modes::parse_mode(mode_arg).expect("Mode was already checked");
+                bench.config.max_errors = mode.get_max_errors();
+                let mode_name = format!("{}", &mode);
+                let start = Instant::now();
+                let maps = prepare::read_and_prepare(&bench.config.input, &bench.config)?;
+                let parsing = start.elapsed();
+                let mut stats = solve(&bench.config, &maps, mode);
+                stats.stages.insert(0, ("parsing", parsing));
+                println!("{}\t{}\t{}\t{}", dataset, mode_name, run, row(&stats, seconds(start.elapsed())));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn row(stats : &RunStats, total : f64) -> String {
+    let times : Vec<String> = STAGE_COLUMNS.iter()
+        .map(|&(_, stage)| format!("{:.4}", stats.seconds(stage)))
+        .collect();
+    format!("{}\t{:.4}\t{}\t{}\t{}\t{}", times.join("\t"), total,
+            stats.search_nodes, stats.candidates_generated, stats.candidates_verified, stats.solutions)
+}
@@ -0,0 +1,154 @@
+use bio::data_structures::bwt::BWT;
+use bio::data_structures::fmindex::{FMIndexable, Interval};
+
+use std::collections::HashSet;
+
+////////////////////////////////////////////////////////////
+
+use structs::run_config::Config;
+use structs::solutions::Candidate;
+use search::{self, GeneratesCandidates, PatternConstants, SuffixConstants};
+use modes::Search;
+
+/*
+A bidirectional FM index, built from an FM index of the text and one of the REVERSED text.
+Matches are represented by a pair of synchronized intervals, one in each index, so that a match
+can be extended with a character on either side (Lam et al. 2009).
+
+As the text is stored backwards (see clarification.txt), extending LEFT is the usual backward search
+and conceptually grows a match forwards. Extending RIGHT conceptually grows a match backwards.
+*/
+pub struct BiFMIndex<'a, F : 'a + FMIndexable, R : 'a + FMIndexable> {
+    fwd : &'a F,
+    rev : &'a R,
+    symbols : Vec<u8>, // every symbol that occurs in the text, in ascending order
+}
+
+/*
+The reverse index is built from this text, which is the text (without its final sentinel)
+reversed, followed by the sentinel. ie: $ZYX$CBA# --> ABC$XYZ$#
+This keeps the sentinel as the unique smallest and last symbol
+*/
+pub fn reversed_text(text : &[u8]) -> Vec<u8> {
+    assert!(text.len() >= 1);
+    let mut rev_text : Vec<u8> = text[..text.len()-1].iter().rev().map(|x| *x).collect();
+    rev_text.push(text[text.len()-1]);
+    rev_text
+}
+
+// a pair of synchronized intervals [lower, lower+size) in the forward and reverse indexes
+#[derive(Debug, Clone, Copy)]
+pub struct BiInterval {
+    pub lower : usize,
+    pub lower_rev : usize,
+    pub size : usize,
+}
+
+impl BiInterval {
+    // the (inclusive) interval in the forward index, as used by the search
+    pub fn forward(&self) -> Interval {
+        assert!(self.size > 0);
+        Interval {
+            lower : self.lower,
+            upper : self.lower + self.size - 1,
+        }
+    }
+}
+
+impl<'a, F : 'a + FMIndexable, R : 'a + FMIndexable> BiFMIndex<'a, F, R> {
+    pub fn new(fwd : &'a F, rev : &'a R, config : &Config) -> Self {
+        assert_eq!(fwd.bwt().len(), rev.bwt().len());
+        let mut symbols = vec![b'#', b'$'];
+        symbols.extend(config.alphabet().iter());
+        symbols.sort();
+        BiFMIndex {
+            fwd : fwd,
+            rev : rev,
+            symbols : symbols,
+        }
+    }
+
+    // the interval of the empty match
+    pub fn full_interval(&self) -> BiInterval {
+        BiInterval {
+            lower : 0,
+            lower_rev : 0,
+            size : self.fwd.bwt().len(),
+        }
+    }
+
+    // match aX for current match X
+    pub fn extend_left(&self, interval : &BiInterval, a : u8) -> BiInterval {
+        let (lower, size, smaller) = extend(self.fwd, &self.symbols, interval.lower, interval.size, a);
+        BiInterval {
+            lower : lower,
+            lower_rev : interval.lower_rev + smaller,
+            size : size,
+        }
+    }
+
+    // match Xa for current match X
+    pub fn extend_right(&self, interval : &BiInterval, a : u8) -> BiInterval {
+        let (lower_rev, size, smaller) = extend(self.rev, &self.symbols, interval.lower_rev, interval.size, a);
+        BiInterval {
+            lower : interval.lower + smaller,
+            lower_rev : lower_rev,
+            size : size,
+        }
+    }
+}
+
+// number of occurrences of a in the BWT of the given index within [lower, lower+size)
+#[inline]
+fn count_in<I : FMIndexable>(index : &I, lower : usize, size : usize, a : u8) -> usize {
+    if size == 0 {
+        return 0;
+    }
+    let before = if lower > 0 { index.occ(lower - 1, a) } else { 0 };
+    index.occ(lower + size - 1, a) - before
+}
+
+/*
+One backward search step for the interval [lower, lower+size) in the given index.
+returns the new lower bound and size in this index, along with the offset of the new interval
+within the old one in the OTHER index. ie: the number of symbols smaller than a in this range.
+*/
+#[inline]
+fn extend<I : FMIndexable>(index : &I, symbols : &[u8], lower : usize, size : usize, a : u8)
+        -> (usize, usize, usize) {
+    let new_lower = index.less(a) + if lower > 0 { index.occ(lower - 1, a) } else { 0 };
+    let new_size = count_in(index, lower, size, a);
+    let mut smaller = 0;
+    for &c in symbols.iter() {
+        if c >= a {
+            break;
+        }
+        smaller += count_in(index, lower, size, c);
+    }
+    (new_lower, new_size, smaller)
+}
+
+impl<'a, F : 'a + FMIndexable, R : 'a + FMIndexable> FMIndexable for BiFMIndex<'a, F, R> {
+    fn occ(&self, r : usize, a : u8) -> usize {
+        self.fwd.occ(r, a)
+    }
+
+    fn less(&self, a : u8) -> usize {
+        self.fwd.less(a)
+    }
+
+    fn bwt(&self) -> &BWT {
+        self.fwd.bwt()
+    }
+}
+
+impl<'a, F : 'a + FMIndexable, R : 'a + FMIndexable> GeneratesCandidates for BiFMIndex<'a, F, R> {
+    fn scheme_candidates(&self,
+                         cand_set : &mut HashSet<Candidate>,
+                         p_cns : &PatternConstants,
+                         s_cns : &SuffixConstants,
+                         p_i : i32,
+                         searches : &[Search]) {
+        search::scheme_candidates(self, cand_set, p_cns, s_cns, p_i, searches);
+    }
+}
@@ -0,0 +1,148 @@
+use std::fs::File;
+use std::io::Read;
+use std::collections::{HashMap, HashSet};
+use toml::Value;
+
+////////////////////////////////////////////////////////////
+
+/*
+The settings of a run, read from a TOML file given with --config.
+Keys are the long names of the options (ie: 'worker_threads = 4', 'reversals = true', 'verbose = 2'),
+and 'input', 'output', 'err_rate' and 'thresh' for the positional arguments.
+The mode is a string as given to -m (ie: 'mode = "kucherov_3"'), or a table of its name and parameters:
+    [mode]
+    name = "kerrors"
+    k = 3
+Values are kept as the strings they would be on the command line, by the name of their argument,
+so that setup interprets them the same way. Arguments given on the command line take precedence,
+but flags can only be added: one set to true here stays set, as there are no options to unset flags.
+*/
+#[derive(Debug, Default)]
+pub struct RunFile {
+    values : HashMap<&'static str, String>,
+    flags : HashSet<&'static str>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Value, // a string or number. arrays are joined with commas, as for --scoring
+    Flag,
+    Count, // a flag that may be repeated, as -vv
+}
+
+// keys of the file, the arguments they stand for, and their kind. keep in line with setup::overlap_args
+static KEYS : &'static [(&'static str, &'static str, Kind)] = &[
+    ("input", "IN_PATH", Kind::Value),
+    ("output", "OUT_PATH", Kind::Value),
+    ("err_rate", "ERR_RATE", Kind::Value),
+    ("thresh", "THRESH", Kind::Value),
+    ("worker_threads", "worker_threads", Kind::Value),
+    ("format_line", "format_line", Kind::Flag),
+    ("provenance", "provenance", Kind::Flag),
+    ("reversals", "reversals", Kind::Flag),
+    ("inclusions", "inclusions", Kind::Flag),
+    ("edit_distance", "edit_distance", Kind::Flag),
+    ("scoring", "scoring", Kind::Value),
+    ("score_rate", "score_rate", Kind::Value),
+    ("stats_json", "stats_json", Kind::Value),
+    ("verbose", "verbose", Kind::Count),
+    ("greedy_output", "greedy_output", Kind::Flag),
+    ("sort_memory", "sort_memory", Kind::Value),
+    ("print", "print", Kind::Flag),
+    ("no_n", "no_n", Kind::Flag),
+    ("track_progress", "track_progress", Kind::Flag),
+    ("gfa", "gfa", Kind::Value),
+    ("unitigs", "unitigs", Kind::Value),
+    ("layout", "layout", Kind::Value),
+    ("consensus", "consensus", Kind::Value),
+    ("fuzz", "fuzz", Kind::Value),
+    ("weak_overlaps", "weak_overlaps", Kind::Value),
+    ("trim_tips", "trim_tips", Kind::Value),
+    ("pop_bubbles", "pop_bubbles", Kind::Value),
+];
+
+impl RunFile {
+    pub fn read(path : &str) -> Result<RunFile, String> {
+        let mut text = String::new();
+        File::open(path).and_then(|mut f| f.read_to_string(&mut text))
+            .map_err(|e| format!("Couldn't read config file '{}': {}", path, e))?;
+        RunFile::parse(&text).map_err(|problem| format!("In config file '{}': {}", path, problem))
+    }
+
+    pub fn parse(text : &str) -> Result<RunFile, String> {
+        let table = match text.parse::<Value>() {
+            Ok(Value::Table(table)) => table,
+            Ok(_) => return Err("Expected a table of settings.".to_owned()),
+            Err(e) => return Err(format!("Couldn't interpret it as TOML: {}", e)),
+        };
+        let mut file = RunFile::default();
+        for (key, value) in table.iter() {
+            if key == "mode" {
+                file.values.insert("mode", mode_arg(value)?);
+                continue;
+            }
+            let &(_, arg, kind) = match KEYS.iter().find(|&&(name, _, _)| name == key) {
+                Some(entry) => entry,
+                None => return Err(format!("Unknown setting '{}'. Settings are named as the long options, \
+                                            besides input, output, err_rate, thresh and mode.", key)),
+            };
+            match (kind, value) {
+                (Kind::Flag, &Value::Boolean(set)) => if set {
+                    file.flags.insert(arg);
+                },
+                (Kind::Count, &Value::Integer(n)) if n >= 0 => {
+                    file.values.insert(arg, n.to_string());
+                },
+                (Kind::Value, &Value::Array(ref items)) => {
+                    let items : Result<Vec<String>, String> = items.iter().map(|x| scalar(key, x)).collect();
+                    file.values.insert(arg, items?.join(","));
+                },
+                (Kind::Value, _) => {
+                    file.values.insert(arg, scalar(key, value)?);
+                },
+                (Kind::Flag, _) => return Err(format!("Setting '{}' must be true or false.", key)),
+                (Kind::Count, _) => return Err(format!("Setting '{}' must be a non-negative integer.", key)),
+            }
+        }
+        Ok(file)
+    }
+
+    // the value of an argument, as a string
+    pub fn value_of(&self, arg : &str) -> Option<&str> {
+        self.values.get(arg).map(|x| &x[..])
+    }
+
+    pub fn is_set(&self, flag : &str) -> bool {
+        self.flags.contains(flag)
+    }
+}
+
+fn scalar(key : &str, value : &Value) -> Result<String, String> {
+    match *value {
+        Value::String(ref s) => Ok(s.clone()),
+        Value::Integer(n) => Ok(n.to_string()),
+        Value::Float(x) => Ok(x.to_string()),
+        _ => Err(format!("Setting '{}' must be a string or a number.", key)),
+    }
+}
+
+// the mode as given to -m. a table is written with named parameters, ie: 'kerrors:k=3'
+fn mode_arg(value : &Value) -> Result<String, String> {
+    let table = match *value {
+        Value::String(ref s) => return Ok(s.clone()),
+        Value::Table(ref table) => table,
+        _ => return Err("Setting 'mode' must be a string or a table.".to_owned()),
+    };
+    let name = match table.get("name") {
+        Some(&Value::String(ref name)) => name,
+        _ => return Err("The mode table needs the 'name' of the mode as a string.".to_owned()),
+    };
+    let mut params = Vec::new();
+    for (param, value) in table.iter().filter(|&(param, _)| param != "name") {
+        match *value {
+            Value::Integer(n) => params.push(format!("{}={}", param, n)),
+            _ => return Err(format!("Parameter '{}' of the mode must be an integer.", param)),
+        }
+    }
+    Ok(format!("{}:{}", name, params.join(",")))
+}
@@ -0,0 +1,134 @@
+use std::io;
+use std::io::{Write, BufWriter};
+use std::fs::File;
+use std::collections::HashMap;
+use std::cmp::min;
+
+////////////////////////////////////////////////////////////
+
+use graph::{StringGraph, Unitig, unitig_name};
+use verification::edit_alignment;
+
+/*
+Polishes unitigs by majority vote. A unitig's sequence is stitched together from its reads, so it keeps their errors.
+Instead, each read is mapped onto the columns of the stitched sequence, and every column takes the symbol most reads agree on.
+The first read spells the start of the sequence. Each next read extends the sequence beyond the previous one,
+so its tail maps onto the sequence directly. The rest of it is aligned to the previous read over their overlap
+with a cheapest banded unit-cost alignment (edit_alignment), so that it maps onto the same columns.
+This is not the verification's modified_levenshtein, which forces the end symbols to be substitutions,
+so its columns can differ slightly from those of the verified overlap.
+Reads vote for their symbol at each column they map to, for a gap at the columns within their span they skip,
+and for the symbols they have between two columns. Such inserted symbols are kept if most reads covering the column have them.
+*/
+
+static SYMBOLS : &'static [u8] = b"ACGTN";
+const GAP : usize = 5;
+
+fn symbol_index(c : u8) -> usize {
+    match c {
+        b'A' => 0,
+        b'C' => 1,
+        b'G' => 2,
+        b'T' => 3,
+        _ => 4,
+    }
+}
+
+pub fn consensus(graph : &StringGraph, unitig : &Unitig) -> Vec<u8> {
+    let len = unitig.seq.len();
+    let mut votes = vec![[0u32; 6]; len];
+    let mut inserted : HashMap<(usize, Vec<u8>), u32> = HashMap::new(); // (after column, symbols)
+    let mut prev_seq : Vec<u8> = Vec::new();
+    let mut prev_map : Vec<Option<usize>> = Vec::new();
+    for (n, &(vertex, _, end)) in unitig.reads.iter().enumerate() {
+        let seq = graph.vertex_seq(vertex);
+        let mut map = vec![None; seq.len()];
+        if n == 0 {
+            for p in 0..seq.len() {
+                map[p] = Some(p);
+            }
+        } else {
+            let arc = &graph.arcs[unitig.arcs[n - 1]];
+            let added = min(arc.len, seq.len());
+            let overlap = seq.len() - added;
+            let prev_overlap = min(arc.overlap_from, prev_seq.len());
+            let prev_start = prev_seq.len() - prev_overlap;
+            for (x, y) in edit_alignment(&prev_seq[prev_start..], &seq[..overlap], arc.errors as usize) {
+                if let (Some(x), Some(y)) = (x, y) {
+                    map[y] = prev_map[prev_start + x];
+                }
+            }
+            for p in overlap..seq.len() {
+                map[p] = Some(end - (seq.len() - p));
+            }
+        }
+
+        let mut last : Option<usize> = None;
+        let mut pending = Vec::new();
+        for p in 0..seq.len() {
+            match map[p] {
+                Some(column) => {
+                    if let Some(last) = last {
+                        for skipped in last+1..column {
+                            votes[skipped][GAP] += 1;
+                        }
+                        if !pending.is_empty() {
+                            *inserted.entry((last, pending.clone())).or_insert(0) += 1;
+                        }
+                    }
+                    pending.clear();
+                    votes[column][symbol_index(seq[p])] += 1;
+                    last = Some(column);
+                },
+                None => if last.is_some() {
+                    pending.push(seq[p]);
+                },
+            }
+        }
+        prev_seq = seq;
+        prev_map = map;
+    }
+
+    // the most common insertion after each column
+    let mut best_inserted : HashMap<usize, (u32, Vec<u8>)> = HashMap::new();
+    for ((column, symbols), count) in inserted.into_iter() {
+        let best = best_inserted.entry(column).or_insert((0, Vec::new()));
+        if (count, &symbols) > (best.0, &best.1) {
+            *best = (count, symbols);
+        }
+    }
+
+    let mut polished = Vec::with_capacity(len);
+    for column in 0..len {
+        let v = &votes[column];
+        // ties are broken in favour of the stitched symbol
+        let mut best = symbol_index(unitig.seq[column]);
+        for i in 0..SYMBOLS.len() {
+            if v[i] > v[best] {
+                best = i;
+            }
+        }
+        if v[best] >= v[GAP] {
+            polished.push(if best == symbol_index(unitig.seq[column]) {unitig.seq[column]} else {SYMBOLS[best]});
+        }
+        if let Some(&(count, ref symbols)) = best_inserted.get(&column) {
+            let coverage : u32 = v.iter().sum();
+            if 2 * count > coverage {
+                polished.extend_from_slice(symbols);
+            }
+        }
+    }
+    polished
+}
+
+// fasta of the polished unitigs, named as by StringGraph::write_unitigs
+pub fn write_consensus(graph : &StringGraph, unitigs : &[Unitig], path : &str) -> Result<(), io::Error> {
+    let mut wrt_buf = BufWriter::new(File::create(path)?);
+    for (i, unitig) in unitigs.iter().enumerate() {
+        let polished = consensus(graph, unitig);
+        write!(wrt_buf, ">{} len={} reads={}\n", unitig_name(i), polished.len(), unitig.reads.len())?;
+        wrt_buf.write_all(&polished)?;
+        wrt_buf.write_all(b"\n")?;
+    }
+    wrt_buf.flush()
+}
@@ -0,0 +1,338 @@
+use std::io;
+use std::io::{Write, BufWriter, BufRead, BufReader};
+use std::fs::File;
+use std::path::Path;
+use std::cmp::{min, max};
+use std::fmt;
+
+////////////////////////////////////////////////////////////
+
+use structs::solutions::Solution;
+use structs::run_config::Maps;
+use useful::Orientation;
+use prepare;
+use tsv;
+use graph::overlap_cigar;
+
+/*
+Converts saved overlaps between the output format (TSV) and the formats of other tools:
+PAF (minimap2), GFA 1, ASQG (SGA) and MHAP. Every format is read into Solutions and written from them.
+The reads are read without reversals, so the ID of a read is its index in the fasta file (as MHAP requires).
+
+The other formats locate an overlap by the intervals it covers in the forward strands of both reads
+(0-based, with exclusive ends, except for ASQG's inclusive ends). For an I overlap, B's interval is mirrored
+to and from the reversed B of the Solution. Each format is read back into the same Solutions it was written from,
+but not every format carries everything: only TSV has the score and identity of --scoring,
+and MHAP only has the errors as a fraction of the overlap.
+Comment lines (as written by --provenance) at the top of the input are carried over to TSV and GFA,
+the formats that have comments.
+*/
+#[derive(Debug)]
+pub struct ConvertConfig {
+    pub input : String,
+    pub output : String,
+    pub reads : String,
+    pub from : Format,
+    pub to : Format,
+    pub n_alphabet : bool, // false for the overlaps of a run with -n, whose reads had their N removed
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Tsv,
+    Paf,
+    Gfa,
+    Asqg,
+    Mhap,
+}
+
+impl Format {
+    // by the given name, or else by the extension of the path
+    pub fn parse(name : Option<&str>, path : &str) -> Result<Format, String> {
+        let name = match name {
+            Some(name) => name.to_owned(),
+            None => Path::new(path).extension().map(|ext| ext.to_string_lossy().into_owned())
+                .ok_or(format!("Couldn't tell the format of '{}' from its extension. Name it explicitly.", path))?,
+        };
+        match &name.to_lowercase()[..] {
+            "tsv" | "txt" => Ok(Format::Tsv),
+            "paf" => Ok(Format::Paf),
+            "gfa" => Ok(Format::Gfa),
+            "asqg" => Ok(Format::Asqg),
+            "mhap" => Ok(Format::Mhap),
+            _ => Err(format!("Unknown overlap format '{}'. Expected one of tsv, paf, gfa, asqg or mhap.", name)),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            Format::Tsv => "TSV",
+            Format::Paf => "PAF",
+            Format::Gfa => "GFA",
+            Format::Asqg => "ASQG",
+            Format::Mhap => "MHAP",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// returns the number of overlaps converted, and of hits skipped as they are no overlaps (see read_solutions)
+pub fn convert(config : &ConvertConfig) -> Result<(usize, usize), io::Error> {
+    let maps = prepare::read_maps(&config.reads, false, config.n_alphabet)?;
+    let (solutions, skipped) = read_solutions(&config.input, config.from, &maps)?;
+    let comments = tsv::read_comments(&config.input)?;
+    write_solutions(&config.output, config.to, &solutions, &maps, &comments)?;
+    Ok((solutions.len(), skipped))
+}
+
+/*
+Reads the overlaps of a file in any format, and how many of its hits were skipped.
+Mappers such as minimap2 also report hits within both reads, which are no suffix-prefix overlaps or inclusions.
+*/
+pub fn read_solutions(path : &str, format : Format, maps : &Maps) -> Result<(Vec<Solution>, usize), io::Error> {
+    if format == Format::Tsv {
+        return Ok((tsv::read_solutions(path, maps)?, 0));
+    }
+    let ids = tsv::ids_by_name(maps);
+    let mut solutions = Vec::new();
+    let mut skipped = 0;
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let bad = || io::Error::new(io::ErrorKind::InvalidData, format!("Couldn't interpret {} line '{}'.", format, line));
+        let fields : Vec<&str> = line.split('\t').collect();
+        let sol = match format {
+            Format::Paf => {
+                if fields.len() < 12 {
+                    return Err(bad());
+                }
+                let num = |i : usize| -> Result<usize, io::Error> {fields[i].parse().map_err(|_| bad())};
+                let (id_a, id_b) = (tsv::id_of(&ids, fields[0])?, tsv::id_of(&ids, fields[5])?);
+                if id_a == id_b {
+                    continue; // a read's overlap with itself (i.e. a repeat) is no suffix-prefix overlap
+                }
+                let orientation = if fields[4] == "-" {Orientation::Reversed} else {Orientation::Normal};
+                // the number of errors is exact in the NM tag, if there is one
+                let errors = match fields[12..].iter().find(|tag| tag.starts_with("NM:i:")) {
+                    Some(tag) => tag[5..].parse().map_err(|_| bad())?,
+                    None => num(10)?.saturating_sub(num(9)?),
+                };
+                from_intervals(maps, id_a, id_b, orientation, (num(2)?, num(3)?), (num(7)?, num(8)?), errors as u32)
+            },
+            Format::Gfa if fields[0] == "L" || fields[0] == "C" => {
+                if fields.len() < 6 || (fields[0] == "C" && fields.len() < 7) {
+                    return Err(bad());
+                }
+                let (id_from, id_to) = (tsv::id_of(&ids, fields[1])?, tsv::id_of(&ids, fields[3])?);
+                let (from_reversed, to_reversed) = (fields[2] == "-", fields[4] == "-");
+                let orientation = if from_reversed == to_reversed {Orientation::Normal} else {Orientation::Reversed};
+                let errors = match fields.iter().find(|tag| tag.starts_with("NM:i:")) {
+                    Some(tag) => tag[5..].parse().map_err(|_| bad())?,
+                    None => 0,
+                };
+                let (len_from, len_to) = (maps.get_length(id_from), maps.get_length(id_to));
+                if fields[0] == "L" {
+                    // the end of the first read (as oriented) overlaps the start of the second
+                    let (ol_from, ol_to) = cigar_lengths(fields[5]).ok_or_else(bad)?;
+                    if ol_from > len_from || ol_to > len_to {
+                        return Err(bad());
+                    }
+                    let from = if from_reversed {(0, ol_from)} else {(len_from - ol_from, len_from)};
+                    let to = if to_reversed {(len_to - ol_to, len_to)} else {(0, ol_to)};
+                    from_intervals(maps, id_from, id_to, orientation, from, to, errors)
+                } else {
+                    // the second read lies within the first, at the given position of the first (as oriented)
+                    let pos : usize = fields[5].parse().map_err(|_| bad())?;
+                    let ol_from = cigar_lengths(fields[6]).map(|(ol_from, _)| ol_from).unwrap_or(len_to);
+                    if pos + ol_from > len_from {
+                        return Err(bad());
+                    }
+                    let from = if from_reversed {(len_from - pos - ol_from, len_from - pos)} else {(pos, pos + ol_from)};
+                    from_intervals(maps, id_from, id_to, orientation, from, (0, len_to), errors)
+                }
+            },
+            Format::Asqg if fields[0] == "ED" => {
+                let ed : Vec<&str> = fields.get(1).ok_or_else(bad)?.split(' ').collect();
+                if ed.len() < 10 {
+                    return Err(bad());
+                }
+                let num = |i : usize| -> Result<usize, io::Error> {ed[i].parse().map_err(|_| bad())};
+                let (id_a, id_b) = (tsv::id_of(&ids, ed[0])?, tsv::id_of(&ids, ed[1])?);
+                let orientation = if ed[8] == "1" {Orientation::Reversed} else {Orientation::Normal};
+                from_intervals(maps, id_a, id_b, orientation, (num(2)?, num(3)? + 1), (num(5)?, num(6)? + 1), num(9)? as u32)
+            },
+            Format::Mhap => {
+                let fields : Vec<&str> = line.split_whitespace().collect();
+                if fields.len() < 12 {
+                    return Err(bad());
+                }
+                let num = |i : usize| -> Result<usize, io::Error> {fields[i].parse().map_err(|_| bad())};
+                let (id_a, id_b) = (num(0)?, num(1)?);
+                if id_a == 0 || id_b == 0 || id_a > maps.num_ids() || id_b > maps.num_ids() {
+                    return Err(bad());
+                }
+                let (id_a, id_b) = (id_a - 1, id_b - 1); // IDs count from 1
+                let orientation = if fields[4] == fields[8] {Orientation::Normal} else {Orientation::Reversed};
+                let (a, b) = ((num(5)?, num(6)?), (num(9)?, num(10)?));
+                let error_rate : f64 = fields[2].parse().map_err(|_| bad())?;
+                let errors = (error_rate * max(a.1 - a.0, b.1 - b.0) as f64).round() as u32;
+                from_intervals(maps, id_a, id_b, orientation, a, b, errors)
+            },
+            _ => continue, // the headers and reads of GFA and ASQG
+        };
+        match sol {
+            Ok(Some(sol)) => solutions.push(sol),
+            Ok(None) => skipped += 1,
+            Err(()) => return Err(bad()),
+        }
+    }
+    Ok((solutions, skipped))
+}
+
+pub fn write_solutions(path : &str, format : Format, solutions : &[Solution], maps : &Maps,
+                       comments : &[String]) -> Result<(), io::Error> {
+    let mut wrt_buf = BufWriter::new(File::create(path)?);
+    if format == Format::Tsv || format == Format::Gfa {
+        for comment in comments.iter() {
+            write!(wrt_buf, "# {}\n", comment)?;
+        }
+    }
+    match format {
+        Format::Gfa => {
+            wrt_buf.write_all(b"H\tVN:Z:1.0\n")?;
+            for id in 0..maps.num_ids() {
+                write!(wrt_buf, "S\t{}\t", maps.get_name_for(id))?;
+                wrt_buf.write_all(&forward_seq(maps, id))?;
+                write!(wrt_buf, "\tLN:i:{}\n", maps.get_length(id))?;
+            }
+        },
+        Format::Asqg => {
+            wrt_buf.write_all(b"HT\tVN:i:1\n")?;
+            for id in 0..maps.num_ids() {
+                write!(wrt_buf, "VT\t{}\t", maps.get_name_for(id))?;
+                wrt_buf.write_all(&forward_seq(maps, id))?;
+                wrt_buf.write_all(b"\n")?;
+            }
+        },
+        _ => (),
+    }
+    for sol in solutions.iter() {
+        let (name_a, name_b) = (maps.get_name_for(sol.id_a), maps.get_name_for(sol.id_b));
+        let (len_a, len_b) = (maps.get_length(sol.id_a), maps.get_length(sol.id_b));
+        let reversed = sol.orientation == Orientation::Reversed;
+        let (a, b) = forward_intervals(sol, len_b);
+        let (oha, ohb) = (sol.overhang_left_a, sol.overhang_right_b);
+        match format {
+            Format::Tsv => write!(wrt_buf, "{}\n", tsv::format_solution(sol, maps))?,
+            Format::Paf => {
+                let block = max(sol.overlap_a, sol.overlap_b);
+                write!(wrt_buf, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t255\tNM:i:{}\n",
+                       name_a, len_a, a.0, a.1, if reversed {'-'} else {'+'},
+                       name_b, len_b, b.0, b.1, block - min(block, sol.errors as usize), block, sol.errors)?;
+            },
+            Format::Gfa => {
+                let b_strand = if reversed {'-'} else {'+'};
+                if oha >= 0 && ohb <= 0 {
+                    write!(wrt_buf, "C\t{}\t+\t{}\t{}\t{}\t{}", name_a, name_b, b_strand, oha,
+                           overlap_cigar(sol.overlap_a, sol.overlap_b))?;
+                } else if oha <= 0 && ohb >= 0 {
+                    // A lies within B. B is given forward, so A is reversed within it for an I overlap
+                    write!(wrt_buf, "C\t{}\t+\t{}\t{}\t{}\t{}", name_b, name_a, b_strand, if reversed {ohb} else {-oha},
+                           overlap_cigar(sol.overlap_b, sol.overlap_a))?;
+                } else if oha > 0 {
+                    write!(wrt_buf, "L\t{}\t+\t{}\t{}\t{}", name_a, name_b, b_strand,
+                           overlap_cigar(sol.overlap_a, sol.overlap_b))?;
+                } else {
+                    write!(wrt_buf, "L\t{}\t{}\t{}\t+\t{}", name_b, b_strand, name_a,
+                           overlap_cigar(sol.overlap_b, sol.overlap_a))?;
+                }
+                write!(wrt_buf, "\tNM:i:{}\n", sol.errors)?;
+            },
+            Format::Asqg => {
+                write!(wrt_buf, "ED\t{} {} {} {} {} {} {} {} {} {}\n",
+                       name_a, name_b, a.0, a.1 - 1, len_a, b.0, b.1 - 1, len_b, if reversed {1} else {0}, sol.errors)?;
+            },
+            Format::Mhap => {
+                // MHAP has no field for the errors, only for their rate. the number of shared k-mers is unknown
+                let error_rate = sol.errors as f64 / max(1, max(sol.overlap_a, sol.overlap_b)) as f64;
+                write!(wrt_buf, "{} {} {:.6} 0 0 {} {} {} {} {} {} {}\n",
+                       sol.id_a + 1, sol.id_b + 1, error_rate, a.0, a.1, len_a, if reversed {1} else {0}, b.0, b.1, len_b)?;
+            },
+        }
+    }
+    wrt_buf.flush()
+}
+
+// the text is stored backwards
+fn forward_seq(maps : &Maps, id : usize) -> Vec<u8> {
+    maps.get_string(id).iter().rev().cloned().collect()
+}
+
+// the intervals the overlap covers in A and in B, both on their forward strands
+fn forward_intervals(sol : &Solution, len_b : usize) -> ((usize, usize), (usize, usize)) {
+    let a_start = max(0, sol.overhang_left_a) as usize;
+    let b_start = max(0, -sol.overhang_left_a) as usize;
+    let b = (b_start, b_start + sol.overlap_b);
+    let b = if sol.orientation == Orientation::Reversed {(len_b - b.1, len_b - b.0)} else {b};
+    ((a_start, a_start + sol.overlap_a), b)
+}
+
+/*
+The inverse of forward_intervals. Puts the read with the smaller name first, as in the output.
+An error if the intervals don't fit in the reads. None if they are no overlap, as they don't reach
+the start of either read (as oriented) and the end of either read.
+*/
+fn from_intervals(maps : &Maps, id_a : usize, id_b : usize, orientation : Orientation,
+                  a : (usize, usize), b : (usize, usize), errors : u32) -> Result<Option<Solution>, ()> {
+    let (len_a, len_b) = (maps.get_length(id_a), maps.get_length(id_b));
+    if a.0 > a.1 || a.1 > len_a || b.0 > b.1 || b.1 > len_b {
+        return Err(());
+    }
+    let b = if orientation == Orientation::Reversed {(len_b - b.1, len_b - b.0)} else {b};
+    if !((a.0 == 0 || b.0 == 0) && (a.1 == len_a || b.1 == len_b)) {
+        return Ok(None);
+    }
+    let mut sol = Solution {
+        id_a : id_a,
+        id_b : id_b,
+        orientation : orientation,
+        overhang_left_a : a.0 as i32 - b.0 as i32,
+        overhang_right_b : (len_b - b.1) as i32 - (len_a - a.1) as i32,
+        overlap_a : a.1 - a.0,
+        overlap_b : b.1 - b.0,
+        errors : errors,
+        score : None,
+        identity : None,
+    };
+    if maps.get_name_for(sol.id_a) > maps.get_name_for(sol.id_b) {
+        // B becomes A. for an I overlap, both are then reversed so that A is not
+        sol.v_flip();
+        if sol.orientation == Orientation::Reversed {
+            sol.mirror_horizontally();
+        }
+    }
+    Ok(Some(sol))
+}
+
+// the lengths of the first and second read that a GFA CIGAR covers. None for '*'
+fn cigar_lengths(cigar : &str) -> Option<(usize, usize)> {
+    if cigar == "*" {
+        return None;
+    }
+    let (mut first, mut second, mut count) = (0, 0, 0);
+    for c in cigar.chars() {
+        match c {
+            _ if c.is_digit(10) => count = count * 10 + c.to_digit(10).unwrap() as usize,
+            'M' | '=' | 'X' => {first += count; second += count; count = 0},
+            'D' | 'N' => {first += count; count = 0},
+            'I' | 'S' => {second += count; count = 0},
+            'H' | 'P' => count = 0,
+            _ => return None,
+        }
+    }
+    Some((first, second))
+}
@@ -0,0 +1,259 @@
+use std::io;
+use std::io::{Write, BufWriter, BufRead, BufReader, Lines};
+use std::fs::{self, File};
+use std::mem;
+use std::cmp::{min, max, Ordering};
+use std::collections::BinaryHeap;
+use std::time::{Instant, Duration};
+
+////////////////////////////////////////////////////////////
+
+use structs::solutions::Solution;
+use structs::run_config::Maps;
+use useful::Orientation;
+use solution_comparator;
+
+/*
+Sorts and deduplicates the solutions of a run (without -g) within a memory budget.
+Solutions are collected in a buffer. Whenever the buffer holds as many solutions as the budget permits,
+it is sorted, deduplicated and spilled to a temporary RUN file next to the output file.
+At the end, the runs and the remaining buffer are combined with a k-way merge, dropping duplicates.
+So that not too many files are open at once, runs are first merged in groups of MAX_FAN_IN into
+longer runs until few enough remain. If nothing was spilled, this is just an in-memory sort.
+*/
+pub struct SolutionSorter<'a> {
+    maps : &'a Maps,
+    buffer : Vec<Solution>,
+    capacity : usize,
+    run_prefix : String,
+    runs : Vec<String>,
+    spilled : usize,
+    next_run : usize, // numbers the run files, including those of merged runs
+    sorting : Duration,
+}
+
+// the most runs merged at once
+pub const MAX_FAN_IN : usize = 64;
+
+// (name of A, name of B, orientation, OHA, OHB, OLA, OLB). the order of the output
+type SortKey<'a> = (&'a str, &'a str, Orientation, i32, i32, usize, usize);
+
+impl<'a> SolutionSorter<'a> {
+    pub fn new(maps : &'a Maps, memory_budget : usize, output : &str) -> SolutionSorter<'a> {
+        let capacity = max(1, memory_budget / mem::size_of::<Solution>());
+        SolutionSorter {
+            maps : maps,
+            buffer : Vec::new(),
+            capacity : capacity,
+            run_prefix : format!("{}.sort_run_", output),
+            runs : Vec::new(),
+            spilled : 0,
+            next_run : 0,
+            sorting : Duration::new(0, 0),
+        }
+    }
+
+    pub fn push(&mut self, sol : Solution) -> Result<(), io::Error> {
+        self.buffer.push(sol);
+        if self.buffer.len() >= self.capacity {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    // the number of runs spilled to disk
+    pub fn runs(&self) -> usize {
+        self.spilled
+    }
+
+    // time spent sorting and deduplicating in memory (not merging)
+    pub fn sorting_time(&self) -> Duration {
+        self.sorting
+    }
+
+    fn sort_buffer(&mut self) {
+        let start = Instant::now();
+        let maps = self.maps;
+        self.buffer.sort_by(|a, b| solution_comparator(a, b, maps));
+        self.buffer.dedup_by(|x, y| solution_comparator(x, y, maps) == Ordering::Equal);
+        self.sorting += start.elapsed();
+    }
+
+    fn spill(&mut self) -> Result<(), io::Error> {
+        self.sort_buffer();
+        let path = self.new_run_path();
+        {
+            let mut wrt_buf = BufWriter::new(File::create(&path)?);
+            for sol in self.buffer.drain(..) {
+                write_record(&mut wrt_buf, &sol)?;
+            }
+            wrt_buf.flush()?;
+        }
+        self.runs.push(path);
+        self.spilled += 1;
+        Ok(())
+    }
+
+    fn new_run_path(&mut self) -> String {
+        self.next_run += 1;
+        format!("{}{}", self.run_prefix, self.next_run - 1)
+    }
+
+    /*
+    Merges consecutive groups of runs into single runs, until a merge of all of them and the buffer
+    keeps no more than MAX_FAN_IN files open. The runs stay in order, so the same duplicates are kept.
+    */
+    fn merge_runs(&mut self) -> Result<(), io::Error> {
+        while self.runs.len() >= MAX_FAN_IN {
+            // runs[..done] are the merged runs of this pass. runs always lists every file, for drop to remove
+            let mut done = 0;
+            while done < self.runs.len() {
+                let end = min(done + MAX_FAN_IN, self.runs.len());
+                if end - done > 1 {
+                    let group = self.runs[done..end].to_vec();
+                    let path = self.new_run_path();
+                    self.runs.insert(end, path.clone());
+                    {
+                        let mut wrt_buf = BufWriter::new(File::create(&path)?);
+                        self.merge(&group, Vec::new(), |sol| write_record(&mut wrt_buf, sol))?;
+                        wrt_buf.flush()?;
+                    }
+                    for old in group.iter() {
+                        fs::remove_file(old)?;
+                    }
+                    self.runs.drain(done..end);
+                }
+                done += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /*
+    Calls emit for every distinct solution in sorted order. Returns how many there were.
+    The run files are removed when the sorter is dropped.
+    */
+    pub fn finish<F : FnMut(&Solution)>(&mut self, mut emit : F) -> Result<usize, io::Error> {
+        self.sort_buffer();
+        if self.runs.is_empty() {
+            for sol in self.buffer.iter() {
+                emit(sol);
+            }
+            return Ok(self.buffer.len());
+        }
+        self.merge_runs()?;
+        let buffer = mem::replace(&mut self.buffer, Vec::new());
+        self.merge(&self.runs, buffer, |sol| {emit(sol); Ok(())})
+    }
+
+    // k-way merge of the given runs and then the buffer, calling emit for each distinct solution
+    fn merge<F>(&self, runs : &[String], buffer : Vec<Solution>, mut emit : F) -> Result<usize, io::Error>
+            where F : FnMut(&Solution) -> Result<(), io::Error> {
+        let maps = self.maps;
+        let key = |sol : &Solution| -> SortKey<'a> {
+            (maps.get_name_for(sol.id_a), maps.get_name_for(sol.id_b), sol.orientation.clone(),
+             sol.overhang_left_a, sol.overhang_right_b, sol.overlap_a, sol.overlap_b)
+        };
+
+        // each source is a run file, except the last, which is the buffer
+        let mut readers : Vec<Lines<BufReader<File>>> = Vec::new();
+        for path in runs.iter() {
+            readers.push(BufReader::new(File::open(path)?).lines());
+        }
+        let mut buffer = buffer.into_iter();
+        let buffer_source = readers.len();
+        let mut heap = BinaryHeap::new();
+        for source in 0..buffer_source+1 {
+            let next = if source == buffer_source {buffer.next()} else {read_record(&mut readers[source])?};
+            if let Some(sol) = next {
+                heap.push(MergeEntry{key : key(&sol), sol : sol, source : source});
+            }
+        }
+
+        let mut count = 0;
+        let mut last : Option<SortKey<'a>> = None;
+        while let Some(MergeEntry{key : k, sol, source}) = heap.pop() {
+            if last.as_ref() != Some(&k) {
+                emit(&sol)?;
+                count += 1;
+                last = Some(k);
+            }
+            let next = if source == buffer_source {buffer.next()} else {read_record(&mut readers[source])?};
+            if let Some(sol) = next {
+                heap.push(MergeEntry{key : key(&sol), sol : sol, source : source});
+            }
+        }
+        Ok(count)
+    }
+}
+
+impl<'a> Drop for SolutionSorter<'a> {
+    fn drop(&mut self) {
+        for path in self.runs.iter() {
+            fs::remove_file(path).ok();
+        }
+    }
+}
+
+// BinaryHeap is a max-heap. The order is reversed so that the smallest key is popped first
+struct MergeEntry<'a> {
+    key : SortKey<'a>,
+    sol : Solution,
+    source : usize,
+}
+
+impl<'a> Ord for MergeEntry<'a> {
+    fn cmp(&self, other : &Self) -> Ordering {
+        // ties are broken by source, so that the earliest duplicate is the one kept
+        (&other.key, other.source).cmp(&(&self.key, self.source))
+    }
+}
+
+impl<'a> PartialOrd for MergeEntry<'a> {
+    fn partial_cmp(&self, other : &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> PartialEq for MergeEntry<'a> {
+    fn eq(&self, other : &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a> Eq for MergeEntry<'a> { }
+
+// one solution per line, with internal IDs. score and identity are '-' if absent
+fn write_record(wrt_buf : &mut BufWriter<File>, sol : &Solution) -> Result<(), io::Error> {
+    write!(wrt_buf, "{} {} {} {} {} {} {} {}",
+           sol.id_a, sol.id_b, sol.orientation, sol.overhang_left_a, sol.overhang_right_b,
+           sol.overlap_a, sol.overlap_b, sol.errors)?;
+    match (sol.score, sol.identity) {
+        (Some(score), Some(identity)) => write!(wrt_buf, " {} {}\n", score, identity),
+        _ => write!(wrt_buf, " - -\n"),
+    }
+}
+
+fn read_record(lines : &mut Lines<BufReader<File>>) -> Result<Option<Solution>, io::Error> {
+    let line = match lines.next() {
+        Some(line) => line?,
+        None => return Ok(None),
+    };
+    let bad = || io::Error::new(io::ErrorKind::InvalidData, format!("Bad record in sorted run: '{}'", line));
+    let fields : Vec<&str> = line.split(' ').collect();
+    if fields.len() != 10 {
+        return Err(bad());
+    }
+    Ok(Some(Solution {
+        id_a : fields[0].parse().map_err(|_| bad())?,
+        id_b : fields[1].parse().map_err(|_| bad())?,
+        orientation : if fields[2] == "N" {Orientation::Normal} else {Orientation::Reversed},
+        overhang_left_a : fields[3].parse().map_err(|_| bad())?,
+        overhang_right_b : fields[4].parse().map_err(|_| bad())?,
+        overlap_a : fields[5].parse().map_err(|_| bad())?,
+        overlap_b : fields[6].parse().map_err(|_| bad())?,
+        errors : fields[7].parse().map_err(|_| bad())?,
+        score : if fields[8] == "-" {None} else {Some(fields[8].parse().map_err(|_| bad())?)},
+        identity : if fields[9] == "-" {None} else {Some(fields[9].parse().map_err(|_| bad())?)},
+    }))
+}
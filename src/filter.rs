@@ -0,0 +1,475 @@
+use std::io;
+use std::io::{Write, BufWriter, BufRead, BufReader};
+use std::fs::File;
+use std::cmp::{max, Ordering, Reverse};
+use std::collections::{HashSet, HashMap};
+
+////////////////////////////////////////////////////////////
+
+use tsv::{self, Overlap};
+use useful::Orientation;
+
+/*
+Filters saved overlaps (in the output format) by an expression on their columns, by the reads they involve,
+and by how many overlaps each read may keep. Lines are written as they were read, header and comments included.
+
+Expressions combine the columns (idA, idB, O, OHA, OHB, OLA, OLB, K, and S and ID with --scoring)
+with numbers, strings in quotes, arithmetic (+ - * /), comparisons (== != < <= > >=),
+logic (&& || !) and parentheses, i.e. 'OLA >= 500 && K/OLA < 0.05 && O == "N"'.
+Numbers are compared as floats, so K/OLA is a rate. Types are checked when parsing.
+*/
+#[derive(Debug)]
+pub struct FilterConfig {
+    pub input : String,
+    pub output : String,
+    pub expression : Option<Expr>,
+    pub max_per_read : Option<usize>, // keeps the longest overlaps of each read
+    pub keep_reads : Option<String>, // path of a list of names. both reads must be in it
+    pub drop_reads : Option<String>, // path of a list of names. neither read may be in it
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    NameA,
+    NameB,
+    Orientation,
+    OverhangA,
+    OverhangB,
+    OverlapA,
+    OverlapB,
+    Errors,
+    Score,
+    Identity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Or, And,
+    Eq, Ne, Lt, Le, Gt, Ge,
+    Add, Sub, Mul, Div,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(f64),
+    Str(String),
+    Column(Column),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Num(f64),
+    Str(String),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Num,
+    Str,
+    Bool,
+}
+
+impl Column {
+    fn parse(name : &str) -> Option<Column> {
+        Some(match name {
+            "idA" => Column::NameA,
+            "idB" => Column::NameB,
+            "O" => Column::Orientation,
+            "OHA" => Column::OverhangA,
+            "OHB" => Column::OverhangB,
+            "OLA" => Column::OverlapA,
+            "OLB" => Column::OverlapB,
+            "K" => Column::Errors,
+            "S" => Column::Score,
+            "ID" => Column::Identity,
+            _ => return None,
+        })
+    }
+
+    fn kind(&self) -> Kind {
+        match *self {
+            Column::NameA | Column::NameB | Column::Orientation => Kind::Str,
+            _ => Kind::Num,
+        }
+    }
+
+    fn value(&self, o : &Overlap) -> Result<Value, String> {
+        let missing = || "S and ID are only in the output of runs with --scoring.".to_owned();
+        Ok(match *self {
+            Column::NameA => Value::Str(o.name_a.clone()),
+            Column::NameB => Value::Str(o.name_b.clone()),
+            Column::Orientation => Value::Str(if o.orientation == Orientation::Normal {"N"} else {"I"}.to_owned()),
+            Column::OverhangA => Value::Num(o.overhang_left_a as f64),
+            Column::OverhangB => Value::Num(o.overhang_right_b as f64),
+            Column::OverlapA => Value::Num(o.overlap_a as f64),
+            Column::OverlapB => Value::Num(o.overlap_b as f64),
+            Column::Errors => Value::Num(o.errors as f64),
+            Column::Score => Value::Num(o.score.ok_or_else(missing)? as f64),
+            Column::Identity => Value::Num(o.identity.ok_or_else(missing)? as f64),
+        })
+    }
+}
+
+impl Expr {
+    // parses a boolean expression
+    pub fn parse(text : &str) -> Result<Expr, String> {
+        let mut parser = Parser{tokens : tokenize(text)?, next : 0};
+        let expr = parser.or()?;
+        if parser.next < parser.tokens.len() {
+            return Err(format!("Unexpected '{}' in the filter expression.", parser.tokens[parser.next]));
+        }
+        match expr.kind()? {
+            Kind::Bool => Ok(expr),
+            _ => Err("The filter expression must be a condition, i.e. a comparison.".to_owned()),
+        }
+    }
+
+    fn kind(&self) -> Result<Kind, String> {
+        match *self {
+            Expr::Num(_) => Ok(Kind::Num),
+            Expr::Str(_) => Ok(Kind::Str),
+            Expr::Column(c) => Ok(c.kind()),
+            Expr::Not(ref x) => expect(x, Kind::Bool, "!").map(|_| Kind::Bool),
+            Expr::Neg(ref x) => expect(x, Kind::Num, "-").map(|_| Kind::Num),
+            Expr::Binary(op, ref x, ref y) => match op {
+                Op::Or | Op::And => {
+                    expect(x, Kind::Bool, op_symbol(op))?;
+                    expect(y, Kind::Bool, op_symbol(op)).map(|_| Kind::Bool)
+                },
+                Op::Add | Op::Sub | Op::Mul | Op::Div => {
+                    expect(x, Kind::Num, op_symbol(op))?;
+                    expect(y, Kind::Num, op_symbol(op)).map(|_| Kind::Num)
+                },
+                Op::Eq | Op::Ne | Op::Lt | Op::Le | Op::Gt | Op::Ge => {
+                    let kind = x.kind()?;
+                    if kind == Kind::Bool {
+                        return Err(format!("'{}' compares numbers or strings, not conditions.", op_symbol(op)));
+                    }
+                    expect(y, kind, op_symbol(op)).map(|_| Kind::Bool)
+                },
+            },
+        }
+    }
+
+    fn eval(&self, o : &Overlap) -> Result<Value, String> {
+        Ok(match *self {
+            Expr::Num(x) => Value::Num(x),
+            Expr::Str(ref x) => Value::Str(x.clone()),
+            Expr::Column(c) => c.value(o)?,
+            Expr::Not(ref x) => Value::Bool(!x.eval(o)?.as_bool()),
+            Expr::Neg(ref x) => Value::Num(-x.eval(o)?.as_num()),
+            Expr::Binary(Op::And, ref x, ref y) => Value::Bool(x.eval(o)?.as_bool() && y.eval(o)?.as_bool()),
+            Expr::Binary(Op::Or, ref x, ref y) => Value::Bool(x.eval(o)?.as_bool() || y.eval(o)?.as_bool()),
+            Expr::Binary(op, ref x, ref y) => {
+                let (x, y) = (x.eval(o)?, y.eval(o)?);
+                let order = match (&x, &y) {
+                    (&Value::Num(a), &Value::Num(b)) => a.partial_cmp(&b),
+                    (&Value::Str(ref a), &Value::Str(ref b)) => Some(a.cmp(b)),
+                    _ => None,
+                };
+                match op {
+                    Op::Add => Value::Num(x.as_num() + y.as_num()),
+                    Op::Sub => Value::Num(x.as_num() - y.as_num()),
+                    Op::Mul => Value::Num(x.as_num() * y.as_num()),
+                    Op::Div => Value::Num(x.as_num() / y.as_num()),
+                    // comparisons with NaN (i.e. 0/0) are false, except !=
+                    Op::Eq => Value::Bool(order == Some(Ordering::Equal)),
+                    Op::Ne => Value::Bool(order != Some(Ordering::Equal)),
+                    Op::Lt => Value::Bool(order == Some(Ordering::Less)),
+                    Op::Le => Value::Bool(order == Some(Ordering::Less) || order == Some(Ordering::Equal)),
+                    Op::Gt => Value::Bool(order == Some(Ordering::Greater)),
+                    Op::Ge => Value::Bool(order == Some(Ordering::Greater) || order == Some(Ordering::Equal)),
+                    Op::And | Op::Or => unreachable!(),
+                }
+            },
+        })
+    }
+
+    pub fn matches(&self, o : &Overlap) -> Result<bool, String> {
+        self.eval(o).map(|v| v.as_bool())
+    }
+}
+
+// the types were checked when parsing
+impl Value {
+    fn as_num(&self) -> f64 {
+        match *self {
+            Value::Num(x) => x,
+            _ => unreachable!(),
+        }
+    }
+
+    fn as_bool(&self) -> bool {
+        match *self {
+            Value::Bool(x) => x,
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn expect(x : &Expr, kind : Kind, op : &str) -> Result<(), String> {
+    let found = x.kind()?;
+    if found == kind {
+        Ok(())
+    } else {
+        Err(format!("'{}' expects {:?} operands, but found {:?}.", op, kind, found).to_lowercase())
+    }
+}
+
+fn op_symbol(op : Op) -> &'static str {
+    match op {
+        Op::Or => "||",
+        Op::And => "&&",
+        Op::Eq => "==",
+        Op::Ne => "!=",
+        Op::Lt => "<",
+        Op::Le => "<=",
+        Op::Gt => ">",
+        Op::Ge => ">=",
+        Op::Add => "+",
+        Op::Sub => "-",
+        Op::Mul => "*",
+        Op::Div => "/",
+    }
+}
+
+// operators, parentheses, numbers, quoted strings and column names
+fn tokenize(text : &str) -> Result<Vec<String>, String> {
+    let chars : Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err("Unterminated string in the filter expression.".to_owned());
+            }
+            i += 1;
+        } else if c.is_digit(10) || c == '.' {
+            while i < chars.len() && (chars[i].is_digit(10) || chars[i] == '.') {
+                i += 1;
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+        } else {
+            let pair : String = chars[i..].iter().take(2).collect();
+            i += match &pair[..] {
+                "&&" | "||" | "==" | "!=" | "<=" | ">=" => 2,
+                _ if "<>!+-*/()".contains(c) => 1,
+                _ => return Err(format!("Unexpected '{}' in the filter expression.", c)),
+            };
+        }
+        tokens.push(chars[start..i].iter().collect());
+    }
+    Ok(tokens)
+}
+
+// recursive descent, from the loosest binding operator to the tightest
+struct Parser {
+    tokens : Vec<String>,
+    next : usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.next).map(|x| &x[..])
+    }
+
+    // the operator next, if it is one of the given
+    fn take_op(&mut self, ops : &[(&str, Op)]) -> Option<Op> {
+        let found = ops.iter().find(|&&(symbol, _)| self.peek() == Some(symbol)).map(|&(_, op)| op);
+        if found.is_some() {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut x = self.and()?;
+        while let Some(op) = self.take_op(&[("||", Op::Or)]) {
+            x = Expr::Binary(op, Box::new(x), Box::new(self.and()?));
+        }
+        Ok(x)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut x = self.not()?;
+        while let Some(op) = self.take_op(&[("&&", Op::And)]) {
+            x = Expr::Binary(op, Box::new(x), Box::new(self.not()?));
+        }
+        Ok(x)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some("!") {
+            self.next += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let x = self.sum()?;
+        let ops = [("==", Op::Eq), ("!=", Op::Ne), ("<", Op::Lt), ("<=", Op::Le), (">", Op::Gt), (">=", Op::Ge)];
+        match self.take_op(&ops) {
+            Some(op) => Ok(Expr::Binary(op, Box::new(x), Box::new(self.sum()?))),
+            None => Ok(x),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut x = self.product()?;
+        while let Some(op) = self.take_op(&[("+", Op::Add), ("-", Op::Sub)]) {
+            x = Expr::Binary(op, Box::new(x), Box::new(self.product()?));
+        }
+        Ok(x)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut x = self.unary()?;
+        while let Some(op) = self.take_op(&[("*", Op::Mul), ("/", Op::Div)]) {
+            x = Expr::Binary(op, Box::new(x), Box::new(self.unary()?));
+        }
+        Ok(x)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some("-") {
+            self.next += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        let token = match self.tokens.get(self.next) {
+            Some(token) => token.clone(),
+            None => return Err("The filter expression ends unexpectedly.".to_owned()),
+        };
+        self.next += 1;
+        let first = token.chars().next().unwrap();
+        if token == "(" {
+            let x = self.or()?;
+            if self.peek() != Some(")") {
+                return Err("Missing ')' in the filter expression.".to_owned());
+            }
+            self.next += 1;
+            Ok(x)
+        } else if first == '"' || first == '\'' {
+            Ok(Expr::Str(token[1..token.len()-1].to_owned()))
+        } else if first.is_digit(10) || first == '.' {
+            token.parse().map(Expr::Num).map_err(|_| format!("Couldn't interpret '{}' as a number.", token))
+        } else {
+            Column::parse(&token).map(Expr::Column).ok_or(format!(
+                "Unknown column '{}' in the filter expression. Expected one of idA, idB, O, OHA, OHB, OLA, OLB, K, S or ID.", token))
+        }
+    }
+}
+
+// one name per line
+fn read_names(path : &str) -> Result<HashSet<String>, io::Error> {
+    let mut names = HashSet::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            names.insert(line.trim().to_owned());
+        }
+    }
+    Ok(names)
+}
+
+/*
+Keeps the overlaps that pass the expression and the name lists, then limits the overlaps per read.
+An overlap is kept by the limit only if it is among the best of both its reads:
+the longest (by the longer of OLA and OLB), then those with the fewest errors, then those first in the file.
+Only with the limit are the passing overlaps held in memory.
+Returns the number of overlaps kept, and the number read.
+*/
+pub fn filter(config : &FilterConfig) -> Result<(usize, usize), io::Error> {
+    let keep_reads = match config.keep_reads {
+        Some(ref path) => Some(read_names(path)?),
+        None => None,
+    };
+    let drop_reads = match config.drop_reads {
+        Some(ref path) => read_names(path)?,
+        None => HashSet::new(),
+    };
+    let allowed = |name : &String| !drop_reads.contains(name) && keep_reads.as_ref().map_or(true, |k| k.contains(name));
+
+    if config.input == config.output {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "The filtered overlaps can't replace their input."));
+    }
+
+    // lines are written as they pass, unless the overlaps per read are limited. Then they're chosen at the end
+    let mut wrt_buf = BufWriter::new(File::create(&config.output)?);
+    let mut lines : Vec<(String, Overlap)> = Vec::new();
+    let mut count = 0;
+    let mut total = 0;
+    for line in BufReader::new(File::open(&config.input)?).lines() {
+        let line = line?;
+        if line.starts_with('#') || (total == 0 && line.starts_with("idA\t")) {
+            // the comments (of --provenance) and the header line
+            write!(wrt_buf, "{}\n", line)?;
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        total += 1;
+        let o = tsv::parse_overlap(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if !allowed(&o.name_a) || !allowed(&o.name_b) {
+            continue;
+        }
+        if let Some(ref expr) = config.expression {
+            if !expr.matches(&o).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))? {
+                continue;
+            }
+        }
+        if config.max_per_read.is_some() {
+            lines.push((line, o));
+        } else {
+            write!(wrt_buf, "{}\n", line)?;
+            count += 1;
+        }
+    }
+
+    if let Some(limit) = config.max_per_read {
+        let mut kept = vec![true; lines.len()];
+        {
+            let mut per_read : HashMap<&str, Vec<usize>> = HashMap::new();
+            for (i, &(_, ref o)) in lines.iter().enumerate() {
+                per_read.entry(&o.name_a).or_insert(Vec::new()).push(i);
+                per_read.entry(&o.name_b).or_insert(Vec::new()).push(i);
+            }
+            for indexes in per_read.values_mut() {
+                indexes.sort_by_key(|&i| {
+                    let o = &lines[i].1;
+                    (Reverse(max(o.overlap_a, o.overlap_b)), o.errors, i)
+                });
+                for &i in indexes.iter().skip(limit) {
+                    kept[i] = false;
+                }
+            }
+        }
+        for (&(ref line, _), _) in lines.iter().zip(kept.iter()).filter(|&(_, &k)| k) {
+            write!(wrt_buf, "{}\n", line)?;
+            count += 1;
+        }
+    }
+    wrt_buf.flush()?;
+    Ok((count, total))
+}
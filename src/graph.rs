@@ -0,0 +1,551 @@
+use std::io;
+use std::io::{Write, BufWriter};
+use std::fs::File;
+use std::collections::{HashMap, HashSet};
+use std::cmp::min;
+
+////////////////////////////////////////////////////////////
+
+use structs::solutions::Solution;
+use structs::run_config::Maps;
+use useful::Orientation;
+use prepare::reverse_complement;
+
+/*
+Builds a string graph from the solutions of a run, as is usually done next when assembling.
+Reads contained in other reads are dropped, as they add nothing to the layout.
+The remaining (dovetail) overlaps become arcs of a bidirected graph, which is represented as a directed graph
+with two vertices per read: one for each strand. Vertex 2r is read r as given, 2r+1 its reverse complement.
+Every overlap yields two arcs, v->w and its complement w'->v'. They are stored side by side,
+so the complement of arc i is arc i^1.
+Transitive arcs are then removed with Myers' algorithm (The fragment assembly string graph, 2005).
+Optionally, the graph is cleaned afterwards of weak overlaps, tips and bubbles, which reads with errors give rise to.
+*/
+#[derive(Debug, Clone)]
+pub struct GraphConfig {
+    pub gfa_path : Option<String>,
+    pub unitigs_path : Option<String>, // fasta
+    pub layout_path : Option<String>,  // the reads of each unitig
+    pub consensus_path : Option<String>, // fasta of the unitigs, polished by consensus.rs
+    pub fuzz : usize, // slack when deciding if an arc is transitive (in symbols)
+    pub weak_ratio : f32,   // overlaps shorter than this fraction of the longest from the same read are removed. 0 disables
+    pub max_tip : usize,    // dead-end paths of at most this many reads are trimmed. 0 disables
+    pub max_bubble : usize, // bubbles with paths of at most this many reads are popped. 0 disables
+}
+
+pub static DEFAULT_FUZZ : usize = 10;
+
+pub struct GraphRead {
+    pub name : String,
+    pub seq : Vec<u8>,
+    pub contained : bool,
+    pub removed : bool, // by cleaning
+}
+
+impl GraphRead {
+    // not part of the graph
+    pub fn dropped(&self) -> bool {
+        self.contained || self.removed
+    }
+}
+
+/*
+Read 'to' follows read 'from'. len is how far 'to' extends beyond the end of 'from'.
+(Not the length of the overlap, but of the symbols the arc adds to a path. Myers calls this the arc's label).
+*/
+#[derive(Debug, Clone)]
+pub struct Arc {
+    pub from : usize,
+    pub to : usize,
+    pub len : usize,
+    pub overlap_from : usize,
+    pub overlap_to : usize,
+    pub errors : u32,
+    pub removed : bool, // as transitive, or by cleaning
+}
+
+/*
+A maximal path of the graph without branches, and the sequence spelled by it.
+For each of its reads: the vertex (giving the read's strand) and the span of the sequence it covers.
+arcs[i] is the arc from reads[i] to reads[i+1].
+*/
+pub struct Unitig {
+    pub reads : Vec<(usize, usize, usize)>,
+    pub arcs : Vec<usize>,
+    pub seq : Vec<u8>,
+}
+
+pub struct StringGraph {
+    pub reads : Vec<GraphRead>,
+    pub arcs : Vec<Arc>,
+}
+
+#[inline]
+pub fn read_of(vertex : usize) -> usize {
+    vertex / 2
+}
+
+#[inline]
+pub fn complement(vertex : usize) -> usize {
+    vertex ^ 1
+}
+
+impl StringGraph {
+    /*
+    The solutions are in the external representation: A is never reversed, B is reversed if the orientation is I.
+    B starts OHA symbols after A starts, and ends OHB symbols after A ends.
+    */
+    pub fn new(maps : &Maps, solutions : &[Solution], reversals : bool) -> StringGraph {
+        let ids_per_read = if reversals {2} else {1};
+        let reads : Vec<GraphRead> = (0..maps.num_ids() / ids_per_read)
+            .map(|r| {
+                let id = r * ids_per_read;
+                GraphRead {
+                    name : maps.get_name_for(id).to_owned(),
+                    seq : maps.get_string(id).iter().rev().cloned().collect(), // the text is stored backwards
+                    contained : false,
+                    removed : false,
+                }
+            })
+            .collect();
+        let mut graph = StringGraph{reads : reads, arcs : Vec::new()};
+
+        let mut dovetails = Vec::new();
+        let mut containments = HashSet::new(); // (outer, inner)
+        for sol in solutions.iter() {
+            let (a, b) = (sol.id_a / ids_per_read, sol.id_b / ids_per_read);
+            let (oha, ohb) = (sol.overhang_left_a, sol.overhang_right_b);
+            // with equal spans, each lies within the other
+            if oha >= 0 && ohb <= 0 {
+                containments.insert((a, b));
+            }
+            if oha <= 0 && ohb >= 0 {
+                containments.insert((b, a));
+            }
+            if oha * ohb > 0 {
+                dovetails.push((a, b, sol));
+            }
+        }
+        // of two reads that lie within one another, the one with the larger name goes
+        for &(outer, inner) in containments.iter() {
+            if !containments.contains(&(inner, outer)) || graph.reads[inner].name > graph.reads[outer].name {
+                graph.reads[inner].contained = true;
+            }
+        }
+
+        // the shortest arc between two vertices is kept (i.e. the longest overlap)
+        let mut best : HashMap<(usize, usize), usize> = HashMap::new();
+        for &(a, b, sol) in dovetails.iter() {
+            if graph.reads[a].contained || graph.reads[b].contained {
+                continue;
+            }
+            let v_a = 2 * a;
+            let v_b = 2 * b + if sol.orientation == Orientation::Normal {0} else {1};
+            let (oha, ohb) = (sol.overhang_left_a, sol.overhang_right_b);
+            let (arc, arc_complement) = if oha > 0 {
+                // A then B
+                (Arc{from : v_a, to : v_b, len : ohb as usize, overlap_from : sol.overlap_a, overlap_to : sol.overlap_b,
+                     errors : sol.errors, removed : false},
+                 Arc{from : complement(v_b), to : complement(v_a), len : oha as usize, overlap_from : sol.overlap_b,
+                     overlap_to : sol.overlap_a, errors : sol.errors, removed : false})
+            } else {
+                // B then A
+                (Arc{from : v_b, to : v_a, len : (-ohb) as usize, overlap_from : sol.overlap_b, overlap_to : sol.overlap_a,
+                     errors : sol.errors, removed : false},
+                 Arc{from : complement(v_a), to : complement(v_b), len : (-oha) as usize, overlap_from : sol.overlap_a,
+                     overlap_to : sol.overlap_b, errors : sol.errors, removed : false})
+            };
+            let key = (arc.from, arc.to);
+            match best.get(&key).cloned() {
+                Some(i) if graph.arcs[i].len <= arc.len => continue,
+                Some(i) => {
+                    graph.arcs[i] = arc;
+                    graph.arcs[i ^ 1] = arc_complement;
+                },
+                None => {
+                    best.insert((arc_complement.from, arc_complement.to), graph.arcs.len() + 1);
+                    best.insert(key, graph.arcs.len());
+                    graph.arcs.push(arc);
+                    graph.arcs.push(arc_complement);
+                },
+            }
+        }
+        graph
+    }
+
+    // for each vertex, the indices of its arcs that are not removed, shortest first
+    pub fn out_arcs(&self) -> Vec<Vec<usize>> {
+        let mut out = vec![Vec::new(); self.reads.len() * 2];
+        for (i, arc) in self.arcs.iter().enumerate() {
+            if !arc.removed {
+                out[arc.from].push(i);
+            }
+        }
+        for arcs in out.iter_mut() {
+            arcs.sort_by_key(|&i| (self.arcs[i].len, self.arcs[i].to));
+        }
+        out
+    }
+
+    /*
+    Myers' transitive reduction. Arc v->x is transitive if there is a path v->w->x
+    of (almost, up to fuzz) the same length. Returns the number of overlaps removed.
+    */
+    pub fn reduce_transitive(&mut self, fuzz : usize) -> usize {
+        const VACANT : u8 = 0;
+        const INPLAY : u8 = 1;
+        const ELIMINATED : u8 = 2;
+        let out = self.out_arcs();
+        let mut mark = vec![VACANT; out.len()];
+        let mut reduce = vec![false; self.arcs.len()];
+        for v in 0..out.len() {
+            if out[v].is_empty() {
+                continue;
+            }
+            for &i in out[v].iter() {
+                mark[self.arcs[i].to] = INPLAY;
+            }
+            let longest = self.arcs[*out[v].last().unwrap()].len + fuzz;
+            for &i in out[v].iter() {
+                let w = self.arcs[i].to;
+                if mark[w] != INPLAY {
+                    continue;
+                }
+                for &j in out[w].iter() {
+                    if self.arcs[j].len + self.arcs[i].len > longest {
+                        break;
+                    }
+                    if mark[self.arcs[j].to] == INPLAY {
+                        mark[self.arcs[j].to] = ELIMINATED;
+                    }
+                }
+            }
+            for &i in out[v].iter() {
+                let w = self.arcs[i].to;
+                for (n, &j) in out[w].iter().enumerate() {
+                    if n > 0 && self.arcs[j].len >= fuzz {
+                        break;
+                    }
+                    if mark[self.arcs[j].to] == INPLAY {
+                        mark[self.arcs[j].to] = ELIMINATED;
+                    }
+                }
+            }
+            for &i in out[v].iter() {
+                if mark[self.arcs[i].to] == ELIMINATED {
+                    reduce[i] = true;
+                }
+                mark[self.arcs[i].to] = VACANT;
+            }
+        }
+        // an arc and its complement go together
+        let mut removed = 0;
+        for i in 0..self.arcs.len() / 2 {
+            let i = 2 * i;
+            if reduce[i] || reduce[i + 1] {
+                self.arcs[i].removed = true;
+                self.arcs[i + 1].removed = true;
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /*
+    Removes an overlap v->w if a longer overlap leaves v, such that v->w overlaps less than ratio times as much.
+    Such weaker overlaps are often spurious, i.e. due to repeats. An overlap is only removed if w is entered by another.
+    Returns the number of overlaps removed.
+    */
+    pub fn remove_weak_overlaps(&mut self, ratio : f32) -> usize {
+        let out = self.out_arcs();
+        let mut in_degree : Vec<usize> = (0..out.len()).map(|v| out[complement(v)].len()).collect();
+        let mut removed = 0;
+        for v in 0..out.len() {
+            let live : Vec<usize> = out[v].iter().cloned().filter(|&i| !self.arcs[i].removed).collect();
+            if live.len() < 2 {
+                continue;
+            }
+            let strongest = live.iter().map(|&i| self.arcs[i].overlap_from).max().unwrap();
+            for &i in live.iter() {
+                let (to, overlap) = (self.arcs[i].to, self.arcs[i].overlap_from);
+                if (overlap as f32) < ratio * strongest as f32 && in_degree[to] > 1 {
+                    self.arcs[i].removed = true;
+                    self.arcs[i ^ 1].removed = true;
+                    in_degree[to] -= 1;
+                    in_degree[complement(v)] -= 1;
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+
+    /*
+    Removes tips: paths of at most max_reads reads without branches that start at a dead end (a vertex without arcs entering it)
+    and join a vertex that is entered by other arcs too. These arise from reads with errors near their ends.
+    Paths that lead nowhere (other than into another dead end) are left alone, as they may be all there is of a region.
+    Returns the number of reads removed.
+    */
+    pub fn trim_tips(&mut self, max_reads : usize) -> usize {
+        let out = self.out_arcs();
+        let mut tips = Vec::new();
+        for v in 0..out.len() {
+            if self.reads[read_of(v)].dropped() || !out[complement(v)].is_empty() {
+                continue;
+            }
+            let mut path = vec![read_of(v)];
+            let mut u = v;
+            while path.len() <= max_reads && out[u].len() == 1 {
+                let w = self.arcs[out[u][0]].to;
+                if out[complement(w)].len() > 1 {
+                    // joins. v..u is a tip
+                    tips.extend(path.drain(..));
+                    break;
+                }
+                path.push(read_of(w));
+                u = w;
+            }
+        }
+        tips.sort();
+        tips.dedup();
+        self.drop_reads(&tips);
+        tips.len()
+    }
+
+    /*
+    Pops simple bubbles: where the paths without branches leaving a vertex v meet again at the same vertex w.
+    Of those with at most max_reads reads between v and w, the path whose overlaps have the fewest errors in total is kept,
+    and the reads of the others (or the overlap, if there are no reads between) are removed.
+    Returns the number of paths removed.
+    */
+    pub fn pop_bubbles(&mut self, max_reads : usize) -> usize {
+        let out = self.out_arcs();
+        let mut claimed = vec![false; self.reads.len()];
+        let mut drop = Vec::new();
+        let mut popped = 0;
+        for v in 0..out.len() {
+            if self.reads[read_of(v)].dropped() || out[v].len() < 2 {
+                continue;
+            }
+            // (end, errors, reads between, first arc)
+            let mut branches : Vec<(usize, u32, Vec<usize>, usize)> = Vec::new();
+            for &first in out[v].iter() {
+                let mut errors = self.arcs[first].errors;
+                let mut u = self.arcs[first].to;
+                let mut between = Vec::new();
+                while between.len() <= max_reads && out[complement(u)].len() == 1 && out[u].len() == 1
+                        && read_of(u) != read_of(v) {
+                    between.push(read_of(u));
+                    errors += self.arcs[out[u][0]].errors;
+                    u = self.arcs[out[u][0]].to;
+                }
+                if between.len() <= max_reads && read_of(u) != read_of(v) {
+                    // the sorted reads break ties the same way from either side of the bubble
+                    let mut key = between.clone();
+                    key.sort();
+                    branches.push((u, errors, key, first));
+                }
+            }
+            branches.sort_by(|x, y| (x.0, x.1, x.2.len(), &x.2).cmp(&(y.0, y.1, y.2.len(), &y.2)));
+            for (n, branch) in branches.iter().enumerate() {
+                if n == 0 || branches[n - 1].0 != branch.0 {
+                    // the best path to this end
+                    for &r in branch.2.iter() {
+                        claimed[r] = true;
+                    }
+                    continue;
+                }
+                let (_, _, ref between, first) = *branch;
+                if between.is_empty() {
+                    if !self.arcs[first].removed {
+                        self.arcs[first].removed = true;
+                        self.arcs[first ^ 1].removed = true;
+                        popped += 1;
+                    }
+                } else if between.iter().all(|&r| !claimed[r]) {
+                    for &r in between.iter() {
+                        claimed[r] = true;
+                    }
+                    drop.extend(between.iter().cloned());
+                    popped += 1;
+                }
+            }
+        }
+        self.drop_reads(&drop);
+        popped
+    }
+
+    // removes the reads and all of their overlaps
+    fn drop_reads(&mut self, reads : &[usize]) {
+        if reads.is_empty() {
+            return;
+        }
+        for &r in reads.iter() {
+            self.reads[r].removed = true;
+        }
+        for i in 0..self.arcs.len() {
+            if self.reads[read_of(self.arcs[i].from)].removed || self.reads[read_of(self.arcs[i].to)].removed {
+                self.arcs[i].removed = true;
+            }
+        }
+    }
+
+    // drops weak overlaps, tips and bubbles as configured
+    pub fn clean(&mut self, config : &GraphConfig) -> (usize, usize, usize) {
+        let weak = if config.weak_ratio > 0.0 {self.remove_weak_overlaps(config.weak_ratio)} else {0};
+        let mut tips = if config.max_tip > 0 {self.trim_tips(config.max_tip)} else {0};
+        let bubbles = if config.max_bubble > 0 {self.pop_bubbles(config.max_bubble)} else {0};
+        if config.max_tip > 0 && bubbles > 0 {
+            // popping can leave new tips behind
+            tips += self.trim_tips(config.max_tip);
+        }
+        (weak, tips, bubbles)
+    }
+
+    pub fn num_removed(&self) -> usize {
+        self.reads.iter().filter(|r| r.removed).count()
+    }
+
+    pub fn num_contained(&self) -> usize {
+        self.reads.iter().filter(|r| r.contained).count()
+    }
+
+    // overlaps (pairs of complementary arcs) that are not removed
+    pub fn num_overlaps(&self) -> usize {
+        self.arcs.iter().filter(|a| !a.removed).count() / 2
+    }
+
+    /*
+    Writes the graph as GFA 1. Contained reads and those removed by cleaning are omitted.
+    Each remaining overlap is one L line, with the alignment of the overlap given as a CIGAR relative to the 'from' read.
+    The comments (ie: of --provenance) are written first, as GFA comment lines.
+    */
+    pub fn write_gfa(&self, path : &str, comments : &[String]) -> Result<(), io::Error> {
+        let mut wrt_buf = BufWriter::new(File::create(path)?);
+        for comment in comments.iter() {
+            write!(wrt_buf, "# {}\n", comment)?;
+        }
+        wrt_buf.write_all(b"H\tVN:Z:1.0\n")?;
+        for read in self.reads.iter().filter(|r| !r.dropped()) {
+            write!(wrt_buf, "S\t{}\t", read.name)?;
+            wrt_buf.write_all(&read.seq)?;
+            write!(wrt_buf, "\tLN:i:{}\n", read.seq.len())?;
+        }
+        for arc in self.arcs.chunks(2).map(|pair| &pair[0]) {
+            if arc.removed {
+                continue;
+            }
+            write!(wrt_buf, "L\t{}\t{}\t{}\t{}\t{}\tNM:i:{}\n",
+                   self.reads[read_of(arc.from)].name, strand(arc.from),
+                   self.reads[read_of(arc.to)].name, strand(arc.to),
+                   overlap_cigar(arc.overlap_from, arc.overlap_to), arc.errors)?;
+        }
+        wrt_buf.flush()
+    }
+
+    // the read as seen from the vertex. odd vertices are reverse complemented
+    pub fn vertex_seq(&self, vertex : usize) -> Vec<u8> {
+        let seq = &self.reads[read_of(vertex)].seq;
+        if vertex % 2 == 0 {
+            seq.clone()
+        } else {
+            reverse_complement(seq)
+        }
+    }
+
+    /*
+    Walks the non-branching paths of the graph. A path continues from v to w if v->w is the only arc leaving v
+    and the only arc entering w. (The arcs entering w are the complements of those leaving w').
+    Paths are walked from their starts first. Whatever remains afterwards lies on cycles.
+    Each unitig is found once, along one of its two strands. Reads without overlaps are unitigs of their own.
+    */
+    pub fn unitigs(&self) -> Vec<Unitig> {
+        let out = self.out_arcs();
+        let mut visited = vec![false; self.reads.len()];
+        let mut unitigs = Vec::new();
+        for &cycles in [false, true].iter() {
+            for v in 0..out.len() {
+                if visited[read_of(v)] || self.reads[read_of(v)].dropped() {
+                    continue;
+                }
+                if !cycles && out[complement(v)].len() == 1 {
+                    let predecessor = complement(self.arcs[out[complement(v)][0]].to);
+                    if out[predecessor].len() == 1 {
+                        continue; // v is within a path
+                    }
+                }
+                unitigs.push(self.walk(v, &out, &mut visited));
+            }
+        }
+        unitigs
+    }
+
+    /*
+    The sequence starts as the first read. Each arc then appends the last 'len' symbols of the next read,
+    being the part of it beyond the end of the previous one.
+    */
+    fn walk(&self, start : usize, out : &[Vec<usize>], visited : &mut [bool]) -> Unitig {
+        let mut seq = self.vertex_seq(start);
+        let mut reads = vec![(start, 0, seq.len())];
+        let mut arcs = Vec::new();
+        visited[read_of(start)] = true;
+        let mut v = start;
+        while out[v].len() == 1 {
+            let arc = &self.arcs[out[v][0]];
+            let w = arc.to;
+            if out[complement(w)].len() != 1 || visited[read_of(w)] {
+                break;
+            }
+            visited[read_of(w)] = true;
+            let w_seq = self.vertex_seq(w);
+            let added = min(arc.len, w_seq.len());
+            seq.extend_from_slice(&w_seq[w_seq.len() - added..]);
+            reads.push((w, seq.len().saturating_sub(w_seq.len()), seq.len()));
+            arcs.push(out[v][0]);
+            v = w;
+        }
+        Unitig{reads : reads, arcs : arcs, seq : seq}
+    }
+
+    // the unitigs as fasta, named by their index
+    pub fn write_unitigs(&self, unitigs : &[Unitig], path : &str) -> Result<(), io::Error> {
+        let mut wrt_buf = BufWriter::new(File::create(path)?);
+        for (i, unitig) in unitigs.iter().enumerate() {
+            write!(wrt_buf, ">{} len={} reads={}\n", unitig_name(i), unitig.seq.len(), unitig.reads.len())?;
+            wrt_buf.write_all(&unitig.seq)?;
+            wrt_buf.write_all(b"\n")?;
+        }
+        wrt_buf.flush()
+    }
+
+    // TSV with a line per read of each unitig, in order: unitig, read, strand, start, end
+    pub fn write_layout(&self, unitigs : &[Unitig], path : &str) -> Result<(), io::Error> {
+        let mut wrt_buf = BufWriter::new(File::create(path)?);
+        for (i, unitig) in unitigs.iter().enumerate() {
+            for &(vertex, start, end) in unitig.reads.iter() {
+                write!(wrt_buf, "{}\t{}\t{}\t{}\t{}\n",
+                       unitig_name(i), self.reads[read_of(vertex)].name, strand(vertex), start, end)?;
+            }
+        }
+        wrt_buf.flush()
+    }
+}
+
+pub fn unitig_name(index : usize) -> String {
+    format!("utg{:06}", index)
+}
+
+fn strand(vertex : usize) -> char {
+    if vertex % 2 == 0 {'+'} else {'-'}
+}
+
+// without the alignment itself, the overlapping sections are described by their lengths only
+pub fn overlap_cigar(overlap_from : usize, overlap_to : usize) -> String {
+    if overlap_from > overlap_to {
+        format!("{}M{}D", overlap_to, overlap_from - overlap_to)
+    } else if overlap_from < overlap_to {
+        format!("{}M{}I", overlap_from, overlap_to - overlap_from)
+    } else {
+        format!("{}M", overlap_from)
+    }
+}
@@ -0,0 +1,64 @@
+use std::cmp::max;
+use std::collections::HashSet;
+
+////////////////////////////////////////////////////////////
+
+use structs::solutions::Solution;
+
+/*
+Removes duplicates from greedy (-g) output without keeping every solution written.
+A solution can only be found by the tasks of the two input strings it involves (with reversals,
+each input string has two tasks: one per text entry). So once all these tasks have completed,
+the solution can never be found again and needn't be remembered.
+Solutions are therefore only kept while one of their strings still has tasks outstanding.
+Those that can no longer recur are purged whenever the set has doubled since the last purge.
+*/
+pub struct SeenSolutions {
+    ids_per_string : usize,
+    tasks_left : Vec<u8>, // for each input string
+    seen : HashSet<Solution>,
+    purge_at : usize,
+}
+
+static MIN_PURGE_AT : usize = 1024;
+
+impl SeenSolutions {
+    pub fn new(num_ids : usize, reversals : bool) -> SeenSolutions {
+        let ids_per_string = if reversals {2} else {1};
+        SeenSolutions {
+            ids_per_string : ids_per_string,
+            tasks_left : vec![ids_per_string as u8; num_ids / ids_per_string],
+            seen : HashSet::new(),
+            purge_at : MIN_PURGE_AT,
+        }
+    }
+
+    /*
+    Registers that the task for the given id has completed with these solutions.
+    Returns those that were not returned before.
+    */
+    pub fn first_seen(&mut self, id : usize, solutions : HashSet<Solution>) -> Vec<Solution> {
+        self.tasks_left[id / self.ids_per_string] -= 1;
+        let mut fresh = Vec::with_capacity(solutions.len());
+        for sol in solutions {
+            if self.seen.contains(&sol) {
+                continue;
+            }
+            if may_recur(&self.tasks_left, self.ids_per_string, &sol) {
+                self.seen.insert(sol.clone());
+            }
+            fresh.push(sol);
+        }
+        if self.seen.len() >= self.purge_at {
+            let (tasks_left, ids_per_string) = (&self.tasks_left, self.ids_per_string);
+            self.seen.retain(|sol| may_recur(tasks_left, ids_per_string, sol));
+            self.purge_at = max(MIN_PURGE_AT, self.seen.len() * 2);
+        }
+        fresh
+    }
+}
+
+// true if a task of either string of the solution is still outstanding
+fn may_recur(tasks_left : &[u8], ids_per_string : usize, sol : &Solution) -> bool {
+    tasks_left[sol.id_a / ids_per_string] > 0 || tasks_left[sol.id_b / ids_per_string] > 0
+}
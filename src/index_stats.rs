@@ -0,0 +1,115 @@
+use std::io;
+use std::fmt;
+use std::mem;
+use std::cmp::{min, max};
+use std::time::{Instant, Duration};
+use bio::alphabets::Alphabet;
+use bio::data_structures::bwt::{bwt, less, Occ};
+use bio::data_structures::suffix_array::suffix_array;
+
+////////////////////////////////////////////////////////////
+
+use structs::run_config::{N_ALPH, ALPH};
+use modes::Mode;
+use prepare;
+use bidirectional;
+use OCC_SAMPLING;
+
+/*
+Builds the index of a fasta file as finding overlaps would, without searching it.
+Reports the reads, how large the index is and how long it takes to build, so that a dataset
+can be checked before a run. The index is rebuilt by every run, as building it takes a small part of the search.
+*/
+#[derive(Debug)]
+pub struct IndexConfig {
+    pub input : String,
+    pub reversals : bool,
+    pub n_alphabet : bool,
+}
+
+#[derive(Debug)]
+pub struct IndexStats {
+    pub reads : usize,
+    pub symbols : usize, // of the reads, not counting reversals
+    pub n_symbols : usize,
+    pub shortest : usize,
+    pub longest : usize,
+    pub text_len : usize, // of the text indexed, with reversals and separators
+    pub reversed_index : bool, // for search schemes
+    pub bytes : Vec<(&'static str, usize)>,
+    pub stages : Vec<(&'static str, Duration)>,
+}
+
+pub fn index_stats(config : &IndexConfig, mode : &Mode) -> Result<IndexStats, io::Error> {
+    let mut stages = Vec::new();
+    let mut stage_start = Instant::now();
+    let maps = prepare::read_maps(&config.input, config.reversals, config.n_alphabet)?;
+    stages.push(("parsing", stage_start.elapsed()));
+    let ids_per_read = if config.reversals {2} else {1};
+    let reads = maps.num_ids() / ids_per_read;
+    let lengths : Vec<usize> = (0..reads).map(|r| maps.get_length(r * ids_per_read)).collect();
+
+    let alphabet_symbols = if config.n_alphabet {N_ALPH} else {ALPH};
+    let alphabet = Alphabet::new(alphabet_symbols);
+    let mut texts = vec![maps.text.clone()];
+    if mode.uses_search_schemes() {
+        texts.push(bidirectional::reversed_text(&maps.text));
+    }
+    let mut bytes = vec![("text", maps.text.len())];
+    for (i, text) in texts.iter().enumerate() {
+        stage_start = Instant::now();
+        let sa = suffix_array(text);
+        stages.push((if i == 0 {"suffix array"} else {"reversed suffix array"}, stage_start.elapsed()));
+        stage_start = Instant::now();
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, OCC_SAMPLING, &alphabet);
+        stages.push((if i == 0 {"bwt and occ"} else {"reversed bwt and occ"}, stage_start.elapsed()));
+        drop((less, occ));
+        // Occ keeps a vector of counts for every OCC_SAMPLING'th position, indexed by symbol
+        let counts = alphabet.max_symbol().unwrap() as usize + 1;
+        let occ_bytes = (bwt.len() / OCC_SAMPLING as usize + 1) * (counts * mem::size_of::<usize>() + mem::size_of::<Vec<usize>>());
+        if i == 0 {
+            bytes.push(("suffix array", sa.len() * mem::size_of::<usize>()));
+            bytes.push(("bwt", bwt.len()));
+            bytes.push(("occ", occ_bytes));
+        } else {
+            // the suffix array of the reversed text is only needed to build its BWT
+            bytes.push(("reversed bwt", bwt.len()));
+            bytes.push(("reversed occ", occ_bytes));
+        }
+    }
+
+    Ok(IndexStats {
+        reads : reads,
+        symbols : lengths.iter().sum(),
+        n_symbols : (0..reads).map(|r| maps.get_string(r * ids_per_read).iter().filter(|&&c| c == b'N').count()).sum(),
+        shortest : lengths.iter().fold(usize::max_value(), |x, &y| min(x, y)),
+        longest : lengths.iter().fold(0, |x, &y| max(x, y)),
+        text_len : maps.text.len(),
+        reversed_index : texts.len() == 2,
+        bytes : bytes,
+        stages : stages,
+    })
+}
+
+impl fmt::Display for IndexStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.reads == 0 {
+            return write!(f, "OK read no reads.\n");
+        }
+        write!(f, "OK read {} reads of {} to {} symbols ({:.1} on average), {} symbols in all, of which {} are N.\n",
+               self.reads, self.shortest, self.longest, self.symbols as f64 / self.reads as f64, self.symbols, self.n_symbols)?;
+        let total : usize = self.bytes.iter().map(|&(_, b)| b).sum();
+        write!(f, "OK indexed a text of {} symbols{}, taking {:.1} MB:\n", self.text_len,
+               if self.reversed_index {" and its reverse"} else {""}, total as f64 / (1024.0 * 1024.0))?;
+        for &(name, b) in self.bytes.iter() {
+            write!(f, "    {:<22}{:>10.1} MB\n", name, b as f64 / (1024.0 * 1024.0))?;
+        }
+        write!(f, "OK stage timings:\n")?;
+        for &(name, duration) in self.stages.iter() {
+            write!(f, "    {:<22}{:>10.3} sec\n", name, duration.as_secs() as f64 + duration.subsec_nanos() as f64 * 1e-9)?;
+        }
+        Ok(())
+    }
+}
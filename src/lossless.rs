@@ -0,0 +1,207 @@
+use std::collections::HashSet;
+use std::cmp::min;
+use std::fmt;
+
+////////////////////////////////////////////////////////////
+
+use modes::IsMode;
+
+/*
+Checks that a mode finds EVERY valid overlap, for all the given settings. For each pattern length, error rate
+and threshold, and for each length of the pattern's overlap, the errors are distributed over the blocks of the pattern
+in every possible way. Each distribution must be found by some filter: its errors must stay within filter_func
+for every block, and candidate_condition must accept the end of the overlap.
+Errors are modelled as substitutions, so the overlaps of A and B have the same length.
+*/
+pub struct CheckRanges {
+    pub patt_lens : (i32, i32), // inclusive
+    pub err_rates : Vec<f32>,
+    pub threshes : (i32, i32), // inclusive
+}
+
+#[derive(Debug)]
+pub enum Problem {
+    BadPartition(String),
+    MissedOverlap {
+        overlap_len : i32,
+        errors_per_block : Vec<i32>, // errors within the overlap for the blocks it spans, from the first
+    },
+}
+
+#[derive(Debug)]
+pub struct Counterexample {
+    pub patt_len : i32,
+    pub err_rate : f32,
+    pub thresh : i32,
+    pub block_lengths : Vec<i32>,
+    pub problem : Problem,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "patt_len {} err_rate {} thresh {} with block lengths {:?}: ",
+               self.patt_len, self.err_rate, self.thresh, &self.block_lengths)?;
+        match self.problem {
+            Problem::BadPartition(ref why) => write!(f, "{}", why),
+            Problem::MissedOverlap{overlap_len, ref errors_per_block} =>
+                write!(f, "no filter finds an overlap of length {} with errors per block {:?}",
+                       overlap_len, errors_per_block),
+        }
+    }
+}
+
+// returns the number of settings checked, or the first problem found
+pub fn check_mode<M : IsMode + ?Sized>(mode : &M, ranges : &CheckRanges) -> Result<usize, Counterexample> {
+    let mut settings = 0;
+    for patt_len in ranges.patt_lens.0..ranges.patt_lens.1+1 {
+        for &err_rate in ranges.err_rates.iter() {
+            for thresh in ranges.threshes.0..ranges.threshes.1+1 {
+                if thresh > patt_len || thresh < 1 || mode.check_settings(err_rate, thresh).is_err() {
+                    continue;
+                }
+                check_setting(mode, patt_len, err_rate, thresh)?;
+                settings += 1;
+            }
+        }
+    }
+    Ok(settings)
+}
+
+fn check_setting<M : IsMode + ?Sized>(mode : &M, patt_len : i32, err_rate : f32, thresh : i32) -> Result<(), Counterexample> {
+    let block_lengths = mode.get_block_lengths(patt_len, err_rate, thresh);
+    let counterexample = |problem| Counterexample {
+        patt_len : patt_len,
+        err_rate : err_rate,
+        thresh : thresh,
+        block_lengths : block_lengths.clone(),
+        problem : problem,
+    };
+    if block_lengths.iter().sum::<i32>() != patt_len {
+        return Err(counterexample(Problem::BadPartition("block lengths don't sum to the pattern length".to_owned())));
+    }
+    if block_lengths.iter().any(|&l| l <= 0) {
+        return Err(counterexample(Problem::BadPartition("blocks must have at least one character".to_owned())));
+    }
+    let mut block_starts = vec![0];
+    for &l in block_lengths.iter() {
+        let next = block_starts[block_starts.len()-1] + l;
+        block_starts.push(next);
+    }
+    for overlap_len in thresh..patt_len+1 {
+        let max_errors = match mode.get_max_errors() {
+            Some(k) => k,
+            None => (err_rate * overlap_len as f32).floor() as i32,
+        };
+        let search = OverlapSearch {
+            mode : mode,
+            block_lengths : &block_lengths,
+            block_starts : &block_starts,
+            thresh : thresh,
+            overlap_len : overlap_len,
+            spanned_blocks : block_starts.iter().filter(|&&s| s < overlap_len).count(),
+        };
+        let mut placed = Vec::new();
+        let mut exhausted = HashSet::new();
+        if search.evade(0, max_errors, &[], &mut placed, &mut exhausted) {
+            return Err(counterexample(Problem::MissedOverlap{
+                overlap_len : overlap_len,
+                errors_per_block : placed,
+            }));
+        }
+    }
+    Ok(())
+}
+
+struct OverlapSearch<'a, M : 'a + IsMode + ?Sized> {
+    mode : &'a M,
+    block_lengths : &'a [i32],
+    block_starts : &'a [i32], // one more than blocks: the last is the pattern length
+    thresh : i32,
+    overlap_len : i32,
+    spanned_blocks : usize,
+}
+
+impl<'a, M : 'a + IsMode + ?Sized> OverlapSearch<'a, M> {
+    #[inline]
+    fn patt_blocks(&self) -> i32 {
+        self.block_lengths.len() as i32
+    }
+
+    #[inline]
+    fn filter_exists(&self, blind_blocks : usize) -> bool {
+        self.patt_blocks() - blind_blocks as i32 >= self.mode.get_fewest_suff_blocks()
+    }
+
+    /*
+    Tries to place at most "remaining" errors in blocks "block" onwards so that no filter finds the overlap.
+    "alive" holds the filters (as their first block) that are still within filter_func, with their errors so far.
+    Returns true if successful, leaving the errors per block in "placed".
+    Remembers the states from which this is impossible in "exhausted".
+    */
+    fn evade(&self, block : usize, remaining : i32, alive : &[(usize, i32)],
+             placed : &mut Vec<i32>, exhausted : &mut HashSet<(usize, i32, Vec<(usize, i32)>)>) -> bool {
+        if block == self.spanned_blocks {
+            return !self.any_filter_finds(alive);
+        }
+        let key = (block, remaining, alive.to_vec());
+        if exhausted.contains(&key) {
+            return false;
+        }
+        let mut starting : Vec<(usize, i32)> = alive.to_vec();
+        if self.filter_exists(block) {
+            starting.push((block, 0));
+        }
+        let chars_in_overlap = min(self.block_lengths[block], self.overlap_len - self.block_starts[block]);
+        for errors in 0..min(chars_in_overlap, remaining)+1 {
+            let still_alive : Vec<(usize, i32)> = starting.iter()
+                .map(|&(first, so_far)| (first, so_far + errors))
+                .filter(|&(first, so_far)| so_far <= self.mode.filter_func(
+                    (block - first) as i32, self.patt_blocks(), first as i32))
+                .collect();
+            let left = remaining - errors;
+            if still_alive.iter().any(|&(first, so_far)| self.surely_finds(first, so_far, block + 1, left)) {
+                continue;
+            }
+            placed.push(errors);
+            if self.evade(block + 1, remaining - errors, &still_alive, placed, exhausted) {
+                return true;
+            }
+            placed.pop();
+        }
+        exhausted.insert(key);
+        false
+    }
+
+    // true if the filter finds the overlap however the remaining errors are placed in the blocks from "block" on
+    fn surely_finds(&self, first : usize, so_far : i32, block : usize, remaining : i32) -> bool {
+        for later in block..self.spanned_blocks {
+            if so_far + remaining > self.mode.filter_func((later - first) as i32, self.patt_blocks(), first as i32) {
+                return false;
+            }
+        }
+        let completed = self.completed_blocks();
+        (so_far..so_far+remaining+1).all(|errors|
+            self.mode.candidate_condition(self.overlap_len, (completed - first) as i32, self.thresh, errors))
+    }
+
+    // blocks that end within the overlap
+    #[inline]
+    fn completed_blocks(&self) -> usize {
+        self.block_starts[1..].iter().filter(|&&end| end <= self.overlap_len).count()
+    }
+
+    fn any_filter_finds(&self, alive : &[(usize, i32)]) -> bool {
+        let completed = self.completed_blocks();
+        for &(first, errors) in alive.iter() {
+            if self.mode.candidate_condition(self.overlap_len, (completed - first) as i32, self.thresh, errors) {
+                return true;
+            }
+        }
+        // a filter starting exactly where the overlap ends matches nothing
+        let next = self.spanned_blocks;
+        next < self.block_lengths.len()
+            && self.block_starts[next] == self.overlap_len
+            && self.filter_exists(next)
+            && self.mode.candidate_condition(self.overlap_len, 0, self.thresh, 0)
+    }
+}
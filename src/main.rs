@@ -11,6 +11,9 @@ use bio::alphabets::Alphabet;
 extern crate clap;
 extern crate num_cpus;
 extern crate cue;
+extern crate rand;
+extern crate toml;
+extern crate md5;
 
 use std::fs::File;
 use std::io::{Write, BufWriter};
@@ -31,13 +34,36 @@ mod structs;
 mod modes;
 mod testing;
 mod useful;
+mod bidirectional;
+mod lossless;
+mod simulate;
+mod benchmark;
+mod external_sort;
+mod greedy_dedup;
+mod graph;
+mod consensus;
+mod tsv;
+mod overlap_stats;
+mod convert;
+mod filter;
+mod index_stats;
+mod config_file;
+mod provenance;
+#[cfg(test)]
+mod oracle;
 
 use structs::solutions::Solution;
 use structs::run_config::{Config, Maps};
+use structs::run_stats::{Counters, RunStats, ReadBlowup, add_elementwise};
 use search::GeneratesCandidates;
+use bidirectional::BiFMIndex;
 use modes::Mode;
+use external_sort::SolutionSorter;
+use greedy_dedup::SeenSolutions;
+use graph::{StringGraph, GraphConfig};
 
 pub static READ_ERR : u8 = b'N';
+pub static OCC_SAMPLING : u32 = 3; // every how many positions of the BWT the occurrences are stored
 static ATOMIC_TASKS_DONE: AtomicUsize = ATOMIC_USIZE_INIT;
 
 /*
@@ -45,20 +71,93 @@ Gets the config and writes all the necessary data into the map struct.
 calls solve() which does all the work
 */
 fn main() {
-    let (mode, config) = setup::parse_run_args();
+    let (mode, config) = match setup::parse_run_args() {
+        setup::Task::Overlaps(mode, config) => (mode, config),
+        setup::Task::VerifyMode(mode, ranges) => {
+            verify_mode(mode, &ranges);
+            return;
+        },
+        setup::Task::Benchmark(bench) => {
+            benchmark::benchmark(bench).expect("Couldn't read or write benchmark data.");
+            return;
+        },
+        setup::Task::Simulate(sim) => {
+            let (reads, overlaps) = simulate::simulate(&sim).expect("Couldn't write simulated data.");
+            println!("OK wrote {} reads to {} and their {} true overlaps to {}.",
+                     reads, sim.reads_path, overlaps, sim.truth_path);
+            return;
+        },
+        setup::Task::Stats(overlaps, reads) => {
+            let overlaps = tsv::read_overlaps(&overlaps).expect("Couldn't read the overlaps.");
+            let names = reads.map(|path| overlap_stats::read_names(&path).expect("Couldn't read the fasta file of reads."));
+            print!("{}", overlap_stats::OverlapStats::new(&overlaps, names));
+            return;
+        },
+        setup::Task::Convert(conv) => {
+            let (overlaps, skipped) = convert::convert(&conv).expect("Couldn't convert the overlaps.");
+            println!("OK converted {} overlaps from {} to {}, written to {}.", overlaps, conv.from, conv.to, conv.output);
+            if skipped > 0 {
+                println!("OK skipped {} hits within both reads, which are no overlaps.", skipped);
+            }
+            return;
+        },
+        setup::Task::Filter(filt) => {
+            let (kept, total) = filter::filter(&filt).expect("Couldn't filter the overlaps.");
+            println!("OK kept {} of {} overlaps, written to {}.", kept, total, filt.output);
+            return;
+        },
+        setup::Task::Index(mode, index) => {
+            print!("{}", index_stats::index_stats(&index, &mode).expect("Couldn't interpret data."));
+            return;
+        },
+        setup::Task::Graph(overlaps, reads, n_alphabet, graph_config) => {
+            let maps = prepare::read_maps(&reads, false, n_alphabet).expect("Couldn't interpret data.");
+            let solutions = tsv::read_solutions(&overlaps, &maps).expect("Couldn't read the overlaps.");
+            let comments = tsv::read_comments(&overlaps).expect("Couldn't read the overlaps.");
+            build_graph(&graph_config, &maps, &solutions, false, 1, &comments);
+            return;
+        },
+    };
     if config.verbosity >= 2 {
         println!("OK interpreted config args.\n{:#?}", &config);
         println!("OK mode set to {}", &mode);
     }
+    // for reproducibility, the effective configuration is kept beside the output. It is for reading, not for --config
+    let config_path = format!("{}.config", config.output);
+    let mut f = File::create(&config_path).expect("Couldn't create the configuration file.");
+    write!(f, "{:#?}\nmode : {}\n", &config, &mode).expect("Couldn't write the configuration file.");
+    if config.verbosity >= 2 {println!("OK configuration written to {}.", config_path);};
+    let parse_start = Instant::now();
     let maps = prepare::read_and_prepare(&config.input, &config)
         .expect("Couldn't interpret data.");
+    let parsing = parse_start.elapsed();
     if config.verbosity >= 2 {
         println!("OK read and mapped fasta input.");
         if !config.n_alphabet{
             println!("OK cleaned 'N' from input strings.");
         }
     };
-    solve(&config, &maps, mode);
+    let mut stats = solve(&config, &maps, mode);
+    stats.stages.insert(0, ("parsing", parsing));
+    if config.verbosity >= 1 {
+        print!("{}", stats);
+    }
+    if let Some(ref path) = config.stats_json {
+        let mut f = File::create(path).expect("Couldn't create the statistics file.");
+        f.write_all(stats.to_json().as_bytes()).expect("Couldn't write the statistics file.");
+        if config.verbosity >= 2 {println!("OK statistics written to {}.", path);};
+    }
+}
+
+// checks the mode for losslessness and reports the outcome
+fn verify_mode(mode : Mode, ranges : &lossless::CheckRanges) {
+    match lossless::check_mode(&*mode, ranges) {
+        Ok(settings) => println!("OK {} is lossless for all {} settings checked.", &mode, settings),
+        Err(counterexample) => {
+            println!("FAILED {} misses overlaps for {}", &mode, counterexample);
+            std::process::exit(1);
+        },
+    }
 }
 
 /*
@@ -68,31 +167,75 @@ fn main() {
 4. spawn workers in a threadpool to solve tasks
 5. write to output either after verification
 */
-fn solve(config : &Config, maps : &Maps, mode : Mode){
+fn solve(config : &Config, maps : &Maps, mode : Mode) -> RunStats {
     let alphabet = Alphabet::new(config.alphabet());
     if config.verbosity >= 2 {
         println!("OK index alphabet set to '{}'",
                  String::from_utf8_lossy(config.alphabet()));
     }
+    let mut stages = Vec::new();
+    let mut stage_start = Instant::now();
     let sa = suffix_array(&maps.text);
+    stages.push(("suffix array", stage_start.elapsed()));
+    stage_start = Instant::now();
     let bwt = bwt(&maps.text, &sa);
     let less = less(&bwt, &alphabet);
-    let occ = Occ::new(&bwt, 3, &alphabet);
+    let occ = Occ::new(&bwt, OCC_SAMPLING, &alphabet);
     let fm = FMIndex::new(&bwt, &less, &occ);
+    stages.push(("bwt and occ", stage_start.elapsed()));
     if config.verbosity >= 2 {println!("OK index ready.");};
 
+    if mode.uses_search_schemes() {
+        // search schemes extend matches in both directions. index the reversed text too
+        stage_start = Instant::now();
+        let rev_text = bidirectional::reversed_text(&maps.text);
+        let rev_sa = suffix_array(&rev_text);
+        stages.push(("reversed suffix array", stage_start.elapsed()));
+        stage_start = Instant::now();
+        let rev_bwt = bio::data_structures::bwt::bwt(&rev_text, &rev_sa);
+        drop(rev_sa);
+        let rev_less = bio::data_structures::bwt::less(&rev_bwt, &alphabet);
+        let rev_occ = Occ::new(&rev_bwt, OCC_SAMPLING, &alphabet);
+        let rev_fm = FMIndex::new(&rev_bwt, &rev_less, &rev_occ);
+        let bi_fm = BiFMIndex::new(&fm, &rev_fm, config);
+        stages.push(("reversed bwt and occ", stage_start.elapsed()));
+        if config.verbosity >= 2 {println!("OK reverse index ready.");};
+        solve_with_index(config, maps, mode, &sa, &bi_fm, stages)
+    } else {
+        solve_with_index(config, maps, mode, &sa, &fm, stages)
+    }
+}
+
+// stages holds the timings of building the index
+fn solve_with_index<G : GeneratesCandidates + Sync>(config : &Config, maps : &Maps, mode : Mode,
+                                                    sa : &RawSuffixArray, fm : &G,
+                                                    mut stages : Vec<(&'static str, std::time::Duration)>) -> RunStats {
     let f = File::create(&config.output)
         .expect("Couldn't open output file.");
     let mut wrt_buf = BufWriter::new(f);
+    let comments = if config.provenance {
+        provenance::provenance(config, maps, &mode).expect("Couldn't read the input to record its provenance.")
+    } else {
+        Vec::new()
+    };
+    for comment in comments.iter() {
+        write!(wrt_buf, "# {}\n", comment).expect("couldn't write provenance to output");
+    }
     if config.format_line{
-        wrt_buf.write_all("idA\tidB\tO\tOHA\tOHB\tOLA\tOLB\tK\n".as_bytes())
+        let header = if config.scoring.is_some() {
+            "idA\tidB\tO\tOHA\tOHB\tOLA\tOLB\tK\tS\tID\n"
+        } else {
+            "idA\tidB\tO\tOHA\tOHB\tOLA\tOLB\tK\n"
+        };
+        wrt_buf.write_all(header.as_bytes())
             .expect("couldn't write header line to output");
         if config.verbosity >= 2 {println!("OK wrote header line to output file.");}
     }
     if config.verbosity >= 2 {println!("OK output writer ready.");}
 
     let id_iterator = 0..maps.num_ids();
-    let mut complete_solution_list : Vec<Solution> = Vec::new(); // used when -g is not used
+    let mut sorter = SolutionSorter::new(maps, config.sort_memory, &config.output); // used when -g is not used
+    let mut seen = SeenSolutions::new(maps.num_ids(), config.reversals);            // used when -g is used
     let config_task_completion_clone = config.track_progress;
     let num_tasks = maps.num_ids();
 
@@ -110,16 +253,23 @@ fn solve(config : &Config, maps : &Maps, mode : Mode){
         println!("OK working.");
     }
     let work_start = Instant::now();
+    let counters = Counters::default();
+    let mut greedy_per_errors = Vec::new();
+    let mut greedy_writing = std::time::Duration::new(0, 0);
     { //borrow block for solution set
-        let computation = |id_a|  solve_an_id(config, maps, id_a, &sa, &fm, &mode);
-        let aggregator = |solutions| {               // aggregation to apply to work results
+        let computation = |id_a|  (id_a, solve_an_id(config, maps, id_a, sa, fm, &mode, &counters));
+        let aggregator = |(id_a, solutions) : (usize, HashSet<Solution>)| {   // aggregation to apply to work results
             if config.greedy_output {
-                //workers ==> out
+                //workers ==> out, skipping solutions another task already found
+                let write_start = Instant::now();
+                let solutions = seen.first_seen(id_a, solutions);
+                add_elementwise(&mut greedy_per_errors, &per_errors(solutions.iter()));
                 for sol in solutions {write_solution(&mut wrt_buf, &sol, maps, config);}
                 wrt_buf.flush().is_ok();
+                greedy_writing += write_start.elapsed();
             }else {
                 //workers ==> solutions --> sorted_solutions --> out
-                for sol in solutions {&mut complete_solution_list.push(sol);}
+                for sol in solutions {sorter.push(sol).expect("Couldn't spill sorted solutions to disk.");}
             }
             if config.track_progress { ATOMIC_TASKS_DONE.fetch_add(1, Ordering::SeqCst);}
         };
@@ -136,23 +286,100 @@ fn solve(config : &Config, maps : &Maps, mode : Mode){
         ATOMIC_TASKS_DONE.store(num_tasks, Ordering::Relaxed);
         progress_tracker.join().is_ok();
     }
+    let search_wall = work_start.elapsed();
 
-    if !config.greedy_output {
-        complete_solution_list.sort_by(|a, b| solution_comparator(a, b, maps));
-        if config.verbosity >= 2 {println!("OK output list sorted.");}
-        complete_solution_list.dedup_by(|x, y| solution_comparator(x, y, maps) == std::cmp::Ordering::Equal);
-        if config.verbosity >= 2 {println!("OK output list deduplicated.");}
-        for sol in complete_solution_list.iter(){
+    let mut stats = if !config.greedy_output {
+        // runs spilled during the search were sorted before this stage, so only the last sort is part of it
+        let spill_sorting = sorter.sorting_time();
+        let stage_start = Instant::now();
+        let mut sorted_per_errors = Vec::new();
+        let written = sorter.finish(|sol| {
+            if sorted_per_errors.len() <= sol.errors as usize {
+                sorted_per_errors.resize(sol.errors as usize + 1, 0);
+            }
+            sorted_per_errors[sol.errors as usize] += 1;
             write_solution(&mut wrt_buf, sol, maps, config);
+        }).expect("Couldn't merge the sorted runs of solutions.");
+        let sorting = sorter.sorting_time();
+        wrt_buf.flush().expect("Couldn't write to the output file.");
+        if config.verbosity >= 2 {
+            println!("OK output list sorted and deduplicated (spilled {} sorted runs to disk).", sorter.runs());
         }
         if config.verbosity >= 1{
-            println!("OK wrote {} solutions.", complete_solution_list.len());
+            println!("OK wrote {} solutions.", written);
         }
-    }
+        let mut stats = RunStats::new(&counters, sorted_per_errors, maps);
+        stats.stages.push(("sorting", sorting));
+        let writing = stage_start.elapsed().checked_sub(sorting - spill_sorting).unwrap_or_default();
+        stats.stages.push(("writing", writing));
+        stats
+    } else {
+        let mut stats = RunStats::new(&counters, greedy_per_errors, maps);
+        stats.stages.push(("writing", greedy_writing));
+        stats
+    };
     if config.verbosity >= 2 {println!("OK output file {} written.", config.output);};
+    if let Some(ref graph_config) = config.graph {
+        // read back from the output, so that the solutions aren't also held in memory while sorting
+        let stage_start = Instant::now();
+        let solutions = tsv::read_solutions(&config.output, maps).expect("Couldn't read back the output.");
+        build_graph(graph_config, maps, &solutions, config.reversals, config.verbosity, &comments);
+        stats.stages.push(("string graph", stage_start.elapsed()));
+    }
     if config.verbosity >= 1{
         println!("OK completed in {}.", approx_elapsed_string(&work_start));
     }
+    stages.push(("search (wall clock)", search_wall));
+    stages.extend(stats.stages.drain(..));
+    stats.stages = stages;
+    stats
+}
+
+// builds, cleans and writes the string graph of the solutions as configured
+// comments are written at the top of the GFA
+fn build_graph(graph_config : &GraphConfig, maps : &Maps, solutions : &[Solution], reversals : bool, verbosity : u8, comments : &[String]) {
+    let mut graph = StringGraph::new(maps, solutions, reversals);
+    let removed = graph.reduce_transitive(graph_config.fuzz);
+    if verbosity >= 1{
+        println!("OK built string graph with {} reads ({} contained reads dropped) and {} overlaps ({} transitive overlaps removed).",
+                 graph.reads.len() - graph.num_contained(), graph.num_contained(), graph.num_overlaps(), removed);
+    }
+    let (weak, tips, bubbles) = graph.clean(graph_config);
+    if verbosity >= 1 && (weak, tips, bubbles) != (0, 0, 0) {
+        println!("OK cleaned string graph of {} weak overlaps, {} reads in tips and {} bubbles, leaving {} reads and {} overlaps.",
+                 weak, tips, bubbles, graph.reads.len() - graph.num_contained() - graph.num_removed(), graph.num_overlaps());
+    }
+    if let Some(ref path) = graph_config.gfa_path {
+        graph.write_gfa(path, comments).expect("Couldn't write the string graph.");
+        if verbosity >= 2 {println!("OK string graph written to {}.", path);}
+    }
+    if graph_config.unitigs_path.is_some() || graph_config.layout_path.is_some() || graph_config.consensus_path.is_some() {
+        let unitigs = graph.unitigs();
+        if let Some(ref path) = graph_config.unitigs_path {
+            graph.write_unitigs(&unitigs, path).expect("Couldn't write the unitigs.");
+        }
+        if let Some(ref path) = graph_config.layout_path {
+            graph.write_layout(&unitigs, path).expect("Couldn't write the unitig layout.");
+        }
+        if let Some(ref path) = graph_config.consensus_path {
+            consensus::write_consensus(&graph, &unitigs, path).expect("Couldn't write the consensus of the unitigs.");
+        }
+        if verbosity >= 1{
+            println!("OK wrote {} unitigs.", unitigs.len());
+        }
+    }
+}
+
+// the number of solutions with each number of errors
+fn per_errors<'a, I : Iterator<Item=&'a Solution>>(solutions : I) -> Vec<usize> {
+    let mut tallies = Vec::new();
+    for sol in solutions {
+        if tallies.len() <= sol.errors as usize {
+            tallies.resize(sol.errors as usize + 1, 0);
+        }
+        tallies[sol.errors as usize] += 1;
+    }
+    tallies
 }
 
 
@@ -236,15 +463,28 @@ essentially converts an ID (and some constant information)
 into a set of solutions involved with that ID.
 */
 #[inline]
-fn solve_an_id<DBWT: DerefBWT + Clone, DLess: DerefLess + Clone, DOcc: DerefOcc + Clone>
+fn solve_an_id<G : GeneratesCandidates>
         (config : &Config, maps : &Maps, id_a : usize, sa : &RawSuffixArray,
-         fm : &FMIndex<DBWT, DLess, DOcc>, mode : &Mode)
+         fm : &G, mode : &Mode, counters : &Counters)
                 -> HashSet<Solution>{
-    let candidates = fm.generate_candidates(maps.get_string(id_a), config, maps, id_a, sa, mode);
+    let start = Instant::now();
+    let candidates = fm.generate_candidates(maps.get_string(id_a), config, maps, id_a, sa, mode, counters);
+    let generated = Instant::now();
+    let num_candidates = candidates.len();
+    counters.candidates_verified.fetch_add(num_candidates, Ordering::Relaxed);
     let solutions = verification::verify_all(id_a, candidates, config, maps);
+    counters.candidates_passed.fetch_add(solutions.len(), Ordering::Relaxed);
+    counters.add_read(ReadBlowup{id : id_a, candidates : num_candidates, solutions : solutions.len()});
+    counters.generation_nanos.fetch_add(nanos(generated - start), Ordering::Relaxed);
+    counters.verification_nanos.fetch_add(nanos(generated.elapsed()), Ordering::Relaxed);
     solutions
 }
 
+#[inline]
+fn nanos(d : std::time::Duration) -> usize {
+    d.as_secs() as usize * 1_000_000_000 + d.subsec_nanos() as usize
+}
+
 
 /*
 writes a single solution to file.
@@ -252,16 +492,8 @@ the written string won't be broken up
 */
 #[inline]
 fn write_solution(buf : &mut BufWriter<File>, s : &Solution, maps : &Maps, config : &Config){
-    let formatted = format!("{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
-                            maps.get_name_for(s.id_a),
-                            maps.get_name_for(s.id_b),
-                            s.orientation,
-                            s.overhang_left_a,
-                            s.overhang_right_b,
-                            s.overlap_a,
-                            s.overlap_b,
-                            s.errors,
-    );
+    let mut formatted = tsv::format_solution(s, maps);
+    formatted.push('\n');
     buf.write(formatted.as_bytes()).is_ok();
     if config.print{
         let a = &String::from_utf8_lossy(maps.get_string(s.id_a));
@@ -0,0 +1,131 @@
+use std::cmp::min;
+use modes::{IsMode, Mode, ModeInfo, ModeParam};
+use std::fmt;
+
+/*
+Partitions for a FIXED limit of K errors per overlap, regardless of the overlap length.
+Every overlap of at least `thresh` characters is guaranteed to complete K+S blocks, even if K indels
+make the pattern's side of it K characters shorter.
+The filtering scheme is otherwise that of Kucherov et al. with the same S parameter.
+*/
+#[derive(Debug)]
+pub struct KErrorsMode {
+    k_param : i32,
+    s_param : i32,
+}
+
+impl KErrorsMode {
+    pub fn new(args : &[&str]) -> Self{
+        if args.len() < 1 || args.len() > 2{
+            panic!("Expecting one or two numeric arguments as the K (error limit) and S parameters!");
+        }
+        let k_param : i32 = args[0].parse()
+            .expect("Couldn't interpret the K argument as a number!");
+        let s_param : i32 = if args.len() == 2 {
+            args[1].parse().expect("Couldn't interpret the S argument as a number!")
+        } else {
+            1
+        };
+        assert!(k_param >= 0, "K-errors mode's K parameter needs to be >= 0");
+        assert!(s_param >= 1, "K-errors mode's S parameter needs to be >= 1");
+        KErrorsMode {k_param : k_param, s_param : s_param}
+    }
+}
+
+pub static INFO : ModeInfo = ModeInfo {
+    name : "kerrors",
+    help : "A fixed limit of K errors per overlap, regardless of its length. ERR_RATE is ignored.",
+    params : &[
+        ModeParam{name : "k", help : "The error limit per overlap", min : 0, default : None},
+        ModeParam{name : "s", help : "Kucherov's S parameter. Overlaps span at least K+S blocks", min : 1, default : Some(1)},
+    ],
+    build : build,
+};
+
+fn build(args : &[&str]) -> Mode {
+    Box::new(KErrorsMode::new(args))
+}
+
+impl fmt::Display for KErrorsMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "K-Errors K={} S={}", self.k_param, self.s_param)
+    }
+}
+
+#[allow(unused_variables)]
+impl IsMode for KErrorsMode {
+    fn get_guaranteed_extra_blocks(&self) -> i32 {
+        self.s_param
+    }
+
+    fn get_fewest_suff_blocks(&self) -> i32{
+        self.s_param
+    }
+
+    fn get_max_errors(&self) -> Option<i32> {
+        Some(self.k_param)
+    }
+
+    fn check_settings(&self, err_rate : f32, thresh : i32) -> Result<(), String> {
+        // K+S prior blocks of at least one symbol each, then K symbols for indels, and the anterior block
+        let shortest = 2*self.k_param + self.s_param + 1;
+        if thresh < shortest {
+            Err(format!("Threshold {} is too short for {}, which splits the first {} symbols of an overlap into {} blocks. \
+                         Use a threshold of at least {}.", thresh, self, thresh - 1 - self.k_param, self.k_param + self.s_param, shortest))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn filter_func(&self, completed_blocks : i32, patt_blocks : i32, blind_blocks : i32) -> i32{
+        min(
+            completed_blocks,
+            patt_blocks - self.s_param,
+        )
+    }
+
+    fn get_block_lengths(&self, patt_len : i32, err_rate : f32, thresh : i32) -> Vec<i32>{
+        if patt_len < thresh{
+            return vec![patt_len];
+        }
+        //the first thresh-1-K chars are split into K+S near-equal PRIOR blocks, which every overlap completes.
+        //with edit distance, A's side of an overlap of length thresh may be K chars shorter.
+        //the remainder forms one ANTERIOR block, which starts inside every overlap of length thresh
+        let prior_blocks = self.k_param + self.s_param;
+        let prior_len = thresh - 1 - self.k_param;
+        // setup rejects shorter thresholds with check_settings, before any pattern is partitioned
+        assert!(prior_len >= prior_blocks,
+                "Threshold {} is too short to be split into {} blocks for K={} S={}",
+                thresh, prior_blocks + 1, self.k_param, self.s_param);
+        let p = prior_len / prior_blocks;
+        let longer_blocks = prior_len - p*prior_blocks;
+
+        let mut block_lengths = Vec::new();
+        for _ in 0..(prior_blocks-longer_blocks){
+            //shorter PRIOR blocks
+            block_lengths.push(p);
+        }
+        for _ in 0..longer_blocks{
+            //longer PRIOR blocks
+            block_lengths.push(p+1);
+        }
+        //ANTERIOR block
+        block_lengths.push(patt_len - prior_len);
+        assert_eq!(block_lengths.iter().sum::<i32>(), patt_len);
+        block_lengths
+    }
+
+    fn candidate_condition(&self,
+            generous_overlap_len : i32,
+            completed_blocks : i32,
+            thresh : i32,
+            errors : i32
+            ) -> bool{
+        let c1 = generous_overlap_len >= thresh;
+        let c2 = completed_blocks > 0;
+        let c3 = completed_blocks >= self.s_param - 1
+            &&
+            errors <= (completed_blocks - self.s_param + 1);
+        c1 && c2 && c3
+    }
+}
@@ -1,5 +1,5 @@
 use std::cmp::{min, max};
-use modes::IsMode;
+use modes::{IsMode, Mode, ModeInfo, ModeParam};
 use std::fmt;
 
 #[derive(Debug)]
@@ -20,6 +20,19 @@ impl KucherovMode {
 }
 
 
+pub static INFO : ModeInfo = ModeInfo {
+    name : "kucherov",
+    help : "Partition and filtering scheme of Kucherov et al. (2014).",
+    params : &[
+        ModeParam{name : "s", help : "Kucherov's S parameter. Overlaps span at least K+S blocks", min : 1, default : Some(2)},
+    ],
+    build : build,
+};
+
+fn build(args : &[&str]) -> Mode {
+    Box::new(KucherovMode::new(args))
+}
+
 impl fmt::Display for KucherovMode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Kucherov S={}", self.s_param)
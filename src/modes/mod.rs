@@ -2,8 +2,46 @@ use std::fmt::{Display, Debug};
 
 pub mod kucherov;
 pub mod valimaki;
+pub mod kerrors;
+pub mod scheme;
+pub mod optimal;
 pub type Mode = Box<IsMode>;
 
+/*
+One search of a bidirectional search scheme, covering one filter.
+"order" lists the blocks of the filter (0 being the filter's first block) in the order they are matched.
+The search starts at some block j, matching it exactly, and extends over the PRECEDING blocks j-1 .. 0.
+It then continues over the SUCCEEDING blocks j+1, j+2 ... as a normal search would.
+"lower" and "upper" bound the total number of errors once each block in "order" is completed.
+Blocks of the filter beyond the end of "order" are matched according to filter_func as usual.
+*/
+#[derive(Debug, Clone)]
+pub struct Search {
+    pub order : Vec<i32>,
+    pub lower : Vec<i32>,
+    pub upper : Vec<i32>,
+}
+
+impl Search {
+    pub fn new(order : Vec<i32>, lower : Vec<i32>, upper : Vec<i32>) -> Self {
+        assert!(order.len() > 0, "Searches must match at least one block");
+        assert!(order.len() == lower.len() && order.len() == upper.len(),
+                "Searches need one lower and one upper bound per block");
+        let start = order[0];
+        for (step, &block) in order.iter().enumerate() {
+            let expected = if step as i32 <= start {start - step as i32} else {step as i32};
+            assert!(block == expected, "Search order {:?} must start at a block, extend over all preceding blocks and then succeeding ones", &order);
+        }
+        assert!(upper[0] == 0, "The first block of a search is always matched exactly");
+        Search {order : order, lower : lower, upper : upper}
+    }
+
+    // the block of the filter this search starts with
+    pub fn start_block(&self) -> i32 {
+        self.order[0]
+    }
+}
+
 /*
 "interface" for new filtering and partition schemes.
 1. Create any struct that implements these functions
@@ -30,40 +68,165 @@ pub trait IsMode: Sync + Display + Debug {
 
     // Used by testing.rs for the cargo testing
     fn get_guaranteed_extra_blocks(&self) -> i32;
+
+    // Modes partitioning for a FIXED number of errors return it here. The run then
+    // searches and verifies against this limit instead of one proportional to the overlap length.
+    fn get_max_errors(&self) -> Option<i32> {
+        None
+    }
+
+    // Modes that can't partition patterns for the given error rate and threshold say why here.
+    // Checked once before a run, so that get_block_lengths may assume it
+    #[allow(unused_variables)]
+    fn check_settings(&self, err_rate : f32, thresh : i32) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Modes that return Some(...) from get_searches need a bidirectional index to be built
+    fn uses_search_schemes(&self) -> bool {
+        false
+    }
+
+    // Optional search scheme for a filter of the given number of blocks.
+    // None means the filter is searched as a whole from its first block, as usual
+    #[allow(unused_variables)]
+    fn get_searches(&self, filter_blocks : i32, patt_blocks : i32) -> Option<Vec<Search>> {
+        None
+    }
 }
 /*
-Add your custom modes in this switch statement so that
-they will be used when the solver is run with the appropriate -m flag arg.
+A numeric parameter of a mode. Parameters are given either positionally, separated by underscores
+(ie: 'kerrors_3_2'), or by name (ie: 'kerrors:k=3,s=2'). Omitted parameters take their default.
 */
-pub fn get_mode(arg : &str) -> Mode {
-    let tokens : Vec<&str> = arg.split('_').collect();
-    if tokens.len() == 0 {
-        panic!("")
-    }
-    let mode_args = &tokens[1..];
-    match tokens[0] {
-        "valimaki" => Box::new(valimaki::ValimakiMode::new()),
-        "kucherov" => Box::new(kucherov::KucherovMode::new(mode_args)),
-        /*
-        NEW MODE OPTIONS GO IN THIS BLOCK
-        CATCH the name you want it to be associated with, whatever you like.
-        return a box contining your IsMode-implementing struct like this:
-            your_mod_rs_file::YourStruct::new(mode_args)
-        ("IsMode" trait is defined above)
-        You can also leave out the mode_args if your new() is defined as requiring no parameter.
-        */
+#[derive(Debug)]
+pub struct ModeParam {
+    pub name : &'static str,
+    pub help : &'static str,
+    pub min : i32,
+    pub default : Option<i32>, // None for parameters that must always be given
+}
 
+/*
+Describes a mode for the registry below. "build" is given the values of all parameters as strings,
+in the order of "params", once they have been validated.
+*/
+pub struct ModeInfo {
+    pub name : &'static str,
+    pub help : &'static str,
+    pub params : &'static [ModeParam],
+    pub build : fn(&[&str]) -> Mode,
+}
 
+/*
+Add your custom modes to this registry so that they will be used when the solver is
+run with the appropriate -m flag arg. I suggest declaring a static ModeInfo in the file of your
+mode, in the fashion of the existing ones, such as kucherov::INFO.
+*/
+pub static REGISTRY : &'static [&'static ModeInfo] = &[
+    &kucherov::INFO,
+    &valimaki::INFO,
+    &kerrors::INFO,
+    &scheme::INFO,
+    &optimal::INFO,
+    // YOUR MODES GO HERE ^^^^
+];
 
+pub const DEFAULT_MODE : &'static str = "kucherov_2";
 
+// parses a mode argument, returning a description of the problem if it is invalid
+pub fn parse_mode(arg : &str) -> Result<Mode, String> {
+    let (name, positional, named) = if let Some(colon) = arg.find(':') {
+        (&arg[..colon], Vec::new(), &arg[colon+1..])
+    } else {
+        let mut tokens = arg.split('_');
+        let name = tokens.next().unwrap();
+        (name, tokens.collect(), "")
+    };
+    let info = match REGISTRY.iter().find(|info| info.name == name) {
+        Some(info) => info,
+        None => return Err(format!("No mode named '{}'. Available modes are: {}.",
+                                   name, REGISTRY.iter().map(|info| info.name).collect::<Vec<_>>().join(", "))),
+    };
+    if positional.len() > info.params.len() {
+        return Err(format!("Mode '{}' expects at most {} parameter(s) but got {}.",
+                           info.name, info.params.len(), positional.len()));
+    }
+    let mut values : Vec<Option<&str>> = vec![None; info.params.len()];
+    for (value, given) in values.iter_mut().zip(positional.iter()) {
+        *value = Some(given);
+    }
+    for assignment in named.split(',').filter(|x| !x.is_empty()) {
+        let mut sides = assignment.splitn(2, '=');
+        let param_name = sides.next().unwrap().trim();
+        let given = match sides.next() {
+            Some(given) => given.trim(),
+            None => return Err(format!("Expected 'NAME=VALUE' for the parameters of mode '{}', but got '{}'.",
+                                       info.name, assignment)),
+        };
+        match info.params.iter().position(|param| param.name == param_name) {
+            Some(index) if values[index].is_some() =>
+                return Err(format!("Parameter '{}' of mode '{}' is given more than once.", param_name, info.name)),
+            Some(index) => values[index] = Some(given),
+            None => return Err(format!("Mode '{}' has no parameter '{}'. Its parameters are: {}.",
+                                       info.name, param_name, describe_param_names(info))),
+        }
+    }
+    let mut args : Vec<String> = Vec::new();
+    for (param, value) in info.params.iter().zip(values.iter()) {
+        let number : i32 = match *value {
+            Some(given) => match given.parse() {
+                Ok(number) => number,
+                Err(_) => return Err(format!("Parameter '{}' of mode '{}' must be an integer, but got '{}'.",
+                                             param.name, info.name, given)),
+            },
+            None => match param.default {
+                Some(number) => number,
+                None => return Err(format!("Mode '{}' requires parameter '{}' ({}).",
+                                           info.name, param.name, param.help)),
+            },
+        };
+        if number < param.min {
+            return Err(format!("Parameter '{}' of mode '{}' must be at least {}, but got {}.",
+                               param.name, info.name, param.min, number));
+        }
+        args.push(number.to_string());
+    }
+    let arg_refs : Vec<&str> = args.iter().map(|x| &x[..]).collect();
+    Ok((info.build)(&arg_refs))
+}
 
+fn describe_param_names(info : &ModeInfo) -> String {
+    if info.params.len() == 0 {
+        "(none)".to_owned()
+    } else {
+        info.params.iter().map(|param| param.name).collect::<Vec<_>>().join(", ")
+    }
+}
 
-        // YOUR MODES GO HERE ^^^^
-        _ => panic!("No mode with the given name found!"),
+// human-readable description of all registered modes and their parameters, for --list_modes
+pub fn describe_modes() -> String {
+    let mut description = String::new();
+    for info in REGISTRY.iter() {
+        description.push_str(&format!("{}\n    {}\n", info.name, info.help));
+        for param in info.params.iter() {
+            let default = match param.default {
+                Some(number) => format!("default {}", number),
+                None => "required".to_owned(),
+            };
+            description.push_str(&format!("    {} : integer >= {} ({}). {}\n",
+                                          param.name, param.min, default, param.help));
+        }
     }
+    description.push_str(&format!("\nThe default mode is '{}'. Parameters are given in order after underscores, \
+                                   or by name ie: 'kerrors_3_2' or 'kerrors:k=3,s=2'.\n", DEFAULT_MODE));
+    description
 }
 
+// like parse_mode, but panics with the problem if the mode argument is invalid
+pub fn get_mode(arg : &str) -> Mode {
+    parse_mode(arg).unwrap_or_else(|problem| panic!("{}", problem))
+}
 
 pub fn default_mode() -> Mode {
-    Box::new(kucherov::KucherovMode::new(&vec!["2"]))
-}
\ No newline at end of file
+    get_mode(DEFAULT_MODE)
+}
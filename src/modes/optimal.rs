@@ -0,0 +1,283 @@
+use std::cmp::{min, max};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::f64;
+use modes::{IsMode, Mode, ModeInfo, ModeParam};
+use std::fmt;
+
+// The cost model assumes a DNA-sized alphabet and an index of roughly this many characters, whatever the input.
+// Only the relative costs of block lengths matter, and these change little with the size of the index
+const ALPHABET_SIZE : f64 = 4.0;
+const EXPECTED_TEXT_LEN : f64 = 10_000_000.0;
+// search nodes deeper than this are not counted. Their expected number is negligible
+const DEPTH_CAP : i32 = 100;
+// blocks up to this length are chosen freely. Longer blocks always extend as far as permitted
+const LONGEST_FREE_BLOCK : i32 = 48;
+// the partition may use at most this many blocks more than are required at any point
+const BLOCK_SLACK : i32 = 2;
+
+/*
+Kucherov's filtering scheme, but the block lengths are chosen to minimise the expected number of
+search nodes, rather than by a closed formula.
+Every block starts a filter, whose expected number of explored nodes is estimated from the length of
+that block. The partition is then chosen by dynamic programming over the positions of the block
+boundaries, subject to every prefix of an overlap spanning enough blocks for Kucherov's lemma.
+Patterns of every length share the partitions of their prefixes, so these are computed once per error rate
+and threshold, and extended as longer patterns come. Partitions are cached per pattern length.
+*/
+#[derive(Debug)]
+pub struct OptimalMode {
+    s_param : i32,
+    block_costs : Vec<f64>, // block_costs[l] is the expected cost of a filter starting with a block of length l
+    cache : Mutex<Cache>,
+}
+
+/*
+prefixes[y] holds lowest(y), then at c - lowest(y) the cost of the cheapest partition of the first y characters
+into c blocks, for c between lowest(y) and highest(y), along with where its last block starts.
+*/
+type Prefixes = Vec<(i32, Vec<Option<(f64, usize)>>)>;
+
+#[derive(Debug, Default)]
+struct Cache {
+    block_lengths : HashMap<(i32, u32, i32), Vec<i32>>,
+    prefixes : HashMap<(u32, i32), Prefixes>, // by error rate and threshold
+}
+
+impl OptimalMode {
+    pub fn new(args : &[&str]) -> Self{
+        if args.len() > 1{
+            panic!("Expecting up to one numeric argument as Kucherov's S parameter!");
+        }
+        let s_param : i32 = if args.len() == 1 {
+            args[0].parse().expect("Couldn't interpret the argument as a number!")
+        } else {
+            2
+        };
+        assert!(s_param >= 1, "Kucherov's S parameter needs to be >= 1");
+        let block_costs = (0..LONGEST_FREE_BLOCK+1)
+            .map(|l| if l == 0 {f64::INFINITY} else {expected_filter_nodes(l)})
+            .collect();
+        OptimalMode {
+            s_param : s_param,
+            block_costs : block_costs,
+            cache : Mutex::new(Cache::default()),
+        }
+    }
+
+    /*
+    the fewest blocks that must be COMPLETED by a prefix of the given length.
+    Like Kucherov's partition, this is generous by rounding the errors up, and starts a character before
+    thresh. So the overlaps of A found with edit distance are covered when B's overlap is a little longer.
+    */
+    #[inline]
+    fn completed_blocks_needed(&self, pref_len : i32, err_rate : f32, thresh : i32) -> i32 {
+        if pref_len < thresh - 1 {
+            0
+        } else {
+            (pref_len as f32 * err_rate).ceil() as i32 + self.s_param - 1
+        }
+    }
+
+    // A block ending at y is still incomplete for the prefix of length y-1, so a partition ending there
+    // needs this many blocks. Never decreases with y
+    fn lowest(&self, y : i32, err_rate : f32, thresh : i32) -> i32 {
+        if y == 0 {
+            0
+        } else {
+            max(
+                self.completed_blocks_needed(y - 1, err_rate, thresh) + 1,
+                self.completed_blocks_needed(y, err_rate, thresh)
+            )
+        }
+    }
+
+    fn highest(&self, y : i32, err_rate : f32, thresh : i32) -> i32 {
+        self.completed_blocks_needed(max(y, thresh), err_rate, thresh) + 1 + BLOCK_SLACK
+    }
+
+    /*
+    the cheapest partitions of the first y characters, from those of every shorter prefix.
+    A block longer than LONGEST_FREE_BLOCK extends as far as its number of blocks permits: to the end of
+    the pattern, or to where one more character would need more blocks.
+    */
+    fn best_ending_at(&self, prefixes : &Prefixes, y : i32, pattern_end : bool, err_rate : f32, thresh : i32)
+                      -> (i32, Vec<Option<(f64, usize)>>) {
+        let first_free = max(0, y - LONGEST_FREE_BLOCK);
+        let lowest = self.lowest(y, err_rate, thresh);
+        (lowest, (lowest .. self.highest(y, err_rate, thresh) + 1).map(|blocks| {
+            let mut long_start = first_free;
+            if pattern_end || blocks < self.lowest(y + 1, err_rate, thresh) {
+                // only prefixes permitted blocks-1 blocks can precede it
+                while long_start > 0 && self.highest(long_start - 1, err_rate, thresh) >= blocks - 1 {
+                    long_start -= 1;
+                }
+            }
+            let mut best = None;
+            for x in (long_start..first_free).chain(first_free..y) {
+                let (x_lowest, ref costs) = prefixes[x as usize];
+                if blocks - 1 < x_lowest {
+                    continue;
+                }
+                let cost = match costs.get((blocks - 1 - x_lowest) as usize) {
+                    Some(&Some((cost, _))) => cost,
+                    _ => continue,
+                };
+                let new_cost = cost + self.block_costs[min(y - x, LONGEST_FREE_BLOCK) as usize];
+                let improves = match best {
+                    Some((old_cost, _)) => new_cost < old_cost,
+                    None => true,
+                };
+                if improves {
+                    best = Some((new_cost, x as usize));
+                }
+            }
+            best
+        }).collect())
+    }
+
+    // check_settings ensures that some partition exists
+    fn optimize(&self, prefixes : &mut Prefixes, patt_len : i32, err_rate : f32, thresh : i32) -> Vec<i32> {
+        if prefixes.is_empty() {
+            prefixes.push((0, vec![Some((0.0, 0))]));
+        }
+        while (prefixes.len() as i32) < patt_len {
+            let y = prefixes.len() as i32;
+            let row = self.best_ending_at(prefixes, y, false, err_rate, thresh);
+            prefixes.push(row);
+        }
+        let (lowest, last_row) = self.best_ending_at(prefixes, patt_len, true, err_rate, thresh);
+
+        // follow the cheapest complete partition back to the start
+        let mut end = None;
+        let mut end_cost = f64::INFINITY;
+        for (offset, entry) in last_row.iter().enumerate() {
+            if let Some((cost, start)) = *entry {
+                if cost < end_cost {
+                    end = Some((lowest + offset as i32, start));
+                    end_cost = cost;
+                }
+            }
+        }
+        let (mut blocks, mut x) = end.expect("No partition has enough blocks within every prefix!");
+        let mut block_lengths = vec![patt_len - x as i32];
+        while x > 0 {
+            blocks -= 1;
+            let y = x;
+            let (y_lowest, ref costs) = prefixes[y];
+            x = costs[(blocks - y_lowest) as usize].unwrap().1;
+            block_lengths.push((y - x) as i32);
+        }
+        block_lengths.reverse();
+        block_lengths
+    }
+}
+
+/*
+Expected number of nodes explored by a (hamming distance) search whose blocks all have the given length,
+when after completing c blocks, c errors are permitted. A node at depth d survives if its string occurs
+in a random text of the expected length.
+*/
+fn expected_filter_nodes(block_len : i32) -> f64 {
+    let mut variants = vec![1.0f64]; // variants[e] : pattern variants with e substitutions at this depth
+    let mut total = 0.0;
+    for depth in 1..DEPTH_CAP+1 {
+        let permitted = (depth / block_len) as usize;
+        let mut next = vec![0.0; min(variants.len() + 1, permitted + 1)];
+        for (e, &count) in variants.iter().enumerate() {
+            if e < next.len() {
+                next[e] += count;
+            }
+            if e + 1 < next.len() {
+                next[e + 1] += count * (ALPHABET_SIZE - 1.0);
+            }
+        }
+        let occurrence = (EXPECTED_TEXT_LEN / ALPHABET_SIZE.powi(depth)).min(1.0);
+        total += next.iter().sum::<f64>() * occurrence;
+        variants = next;
+    }
+    total
+}
+
+pub static INFO : ModeInfo = ModeInfo {
+    name : "optimal",
+    help : "Kucherov's filters with block lengths optimized for the expected number of search nodes. \
+            The costs are modelled on a DNA index of 10 million characters, whatever the input.",
+    params : &[
+        ModeParam{name : "s", help : "Kucherov's S parameter", min : 1, default : Some(2)},
+    ],
+    build : build,
+};
+
+fn build(args : &[&str]) -> Mode {
+    Box::new(OptimalMode::new(args))
+}
+
+impl fmt::Display for OptimalMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Optimal partition S={}", self.s_param)
+    }
+}
+
+#[allow(unused_variables)]
+impl IsMode for OptimalMode {
+    fn get_guaranteed_extra_blocks(&self) -> i32 {
+        self.s_param
+    }
+
+    fn get_fewest_suff_blocks(&self) -> i32{
+        self.s_param
+    }
+
+    fn filter_func(&self, completed_blocks : i32, patt_blocks : i32, blind_blocks : i32) -> i32{
+        min(
+            completed_blocks,
+            patt_blocks - self.s_param,
+        )
+    }
+
+    // a prefix of y characters completes at most y blocks. Beyond thresh, prefixes need fewer blocks per character
+    fn check_settings(&self, err_rate : f32, thresh : i32) -> Result<(), String> {
+        for y in 1..thresh+1 {
+            let needed = self.lowest(y, err_rate, thresh);
+            if needed > y {
+                return Err(format!("{} can't partition overlaps with error rate {} and threshold {}, as their first {} symbols \
+                                    would need {} blocks. Use a longer threshold or a lower error rate.",
+                                   self, err_rate, thresh, y, needed));
+            }
+        }
+        Ok(())
+    }
+
+    fn get_block_lengths(&self, patt_len : i32, err_rate : f32, thresh : i32) -> Vec<i32>{
+        if patt_len < thresh{
+            return vec![patt_len];
+        }
+        let key = (patt_len, err_rate.to_bits(), thresh);
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(block_lengths) = cache.block_lengths.get(&key) {
+            return block_lengths.clone();
+        }
+        let block_lengths = {
+            let prefixes = cache.prefixes.entry((err_rate.to_bits(), thresh)).or_insert_with(Vec::new);
+            self.optimize(prefixes, patt_len, err_rate, thresh)
+        };
+        assert_eq!(block_lengths.iter().sum::<i32>(), patt_len);
+        cache.block_lengths.insert(key, block_lengths.clone());
+        block_lengths
+    }
+
+    fn candidate_condition(&self,
+            generous_overlap_len : i32,
+            completed_blocks : i32,
+            thresh : i32,
+            errors : i32
+            ) -> bool{
+        let c1 = generous_overlap_len >= thresh;
+        let c2 = completed_blocks > 0;
+        let c3 = completed_blocks >= self.s_param - 1
+            &&
+            errors <= (completed_blocks - self.s_param + 1);
+        c1 && c2 && c3
+    }
+}
@@ -0,0 +1,113 @@
+use modes::{IsMode, Mode, ModeInfo, ModeParam, Search};
+use modes::kucherov::KucherovMode;
+use std::fmt;
+
+/*
+The partition and filtering scheme of Kucherov et al., but each filter is searched by a
+bidirectional search scheme rather than a single search from its first block.
+
+Kucherov's filters permit at most c errors once c blocks are completed. So errors (if any)
+first occur in the second block, and blocks are then erroneous one error at a time.
+The searches split on the first of the filter's blocks 1..=depth to be matched exactly:
+ > If it is block t+1, the search starts there exactly, and extends over the preceding blocks
+   with exactly one error in each of blocks t .. 1, and none in block 0.
+ > If there is none, a search from block 0 requires exactly one error in each of blocks 1..=depth.
+Beyond these blocks, all searches proceed as the unidirectional search would.
+Starting with an exact block prunes most branches before errors are permitted.
+*/
+#[derive(Debug)]
+pub struct SchemeMode {
+    kucherov : KucherovMode,
+    s_param : i32,
+    depth : i32,
+}
+
+impl SchemeMode {
+    pub fn new(args : &[&str]) -> Self{
+        if args.len() > 2{
+            panic!("Expecting up to two numeric arguments as Kucherov's S parameter and the search depth!");
+        }
+        let s_arg = if args.len() >= 1 {args[0]} else {"2"};
+        let depth : i32 = if args.len() == 2 {
+            args[1].parse().expect("Couldn't interpret the depth argument as a number!")
+        } else {
+            2
+        };
+        assert!(depth >= 1, "The search scheme depth needs to be >= 1");
+        SchemeMode {
+            kucherov : KucherovMode::new(&[s_arg]),
+            s_param : s_arg.parse().unwrap(),
+            depth : depth,
+        }
+    }
+}
+
+pub static INFO : ModeInfo = ModeInfo {
+    name : "scheme",
+    help : "Kucherov's partition and filters, searched with bidirectional search schemes.",
+    params : &[
+        ModeParam{name : "s", help : "Kucherov's S parameter", min : 1, default : Some(2)},
+        ModeParam{name : "depth", help : "Number of leading blocks of each filter covered by the search scheme", min : 1, default : Some(2)},
+    ],
+    build : build,
+};
+
+fn build(args : &[&str]) -> Mode {
+    Box::new(SchemeMode::new(args))
+}
+
+impl fmt::Display for SchemeMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Search schemes S={} depth={}", self.s_param, self.depth)
+    }
+}
+
+impl IsMode for SchemeMode {
+    fn get_guaranteed_extra_blocks(&self) -> i32 {
+        self.kucherov.get_guaranteed_extra_blocks()
+    }
+
+    fn get_fewest_suff_blocks(&self) -> i32{
+        self.kucherov.get_fewest_suff_blocks()
+    }
+
+    fn filter_func(&self, completed_blocks : i32, patt_blocks : i32, blind_blocks : i32) -> i32{
+        self.kucherov.filter_func(completed_blocks, patt_blocks, blind_blocks)
+    }
+
+    fn get_block_lengths(&self, patt_len : i32, err_rate : f32, thresh : i32) -> Vec<i32>{
+        self.kucherov.get_block_lengths(patt_len, err_rate, thresh)
+    }
+
+    fn candidate_condition(&self, generous_overlap_len : i32, completed_blocks : i32, thresh : i32, errors : i32) -> bool{
+        self.kucherov.candidate_condition(generous_overlap_len, completed_blocks, thresh, errors)
+    }
+
+    fn uses_search_schemes(&self) -> bool {
+        true
+    }
+
+    fn get_searches(&self, filter_blocks : i32, patt_blocks : i32) -> Option<Vec<Search>> {
+        //blocks 1..=depth may each have exactly one error only while the filter permits it
+        let mut depth = 0;
+        while depth < self.depth && depth + 1 < filter_blocks
+                && self.filter_func(depth + 1, patt_blocks, 0) >= depth + 1 {
+            depth += 1;
+        }
+        if depth == 0 {
+            return None;
+        }
+        let mut searches = Vec::new();
+        for t in 0..depth {
+            // blocks 1..=t have one error each. block t+1 is exact
+            let order : Vec<i32> = (0..t+2).rev().collect();
+            let mut bounds : Vec<i32> = (0..t+1).collect();
+            bounds.push(t);
+            searches.push(Search::new(order, bounds.clone(), bounds));
+        }
+        // blocks 1..=depth have one error each
+        let order : Vec<i32> = (0..depth+1).collect();
+        searches.push(Search::new(order.clone(), order.clone(), order));
+        Some(searches)
+    }
+}
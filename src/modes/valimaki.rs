@@ -1,5 +1,5 @@
 use std::cmp::{min};
-use modes::IsMode;
+use modes::{IsMode, Mode, ModeInfo};
 use std::fmt;
 
 #[derive(Debug)]
@@ -11,6 +11,18 @@ impl ValimakiMode {
     }
 }
 
+pub static INFO : ModeInfo = ModeInfo {
+    name : "valimaki",
+    help : "Partition and filtering scheme of Valimaki et al. (2012).",
+    params : &[],
+    build : build,
+};
+
+#[allow(unused_variables)]
+fn build(args : &[&str]) -> Mode {
+    Box::new(ValimakiMode::new())
+}
+
 /*
 According to the Valimaki algorithm in the 2012 paper
 */
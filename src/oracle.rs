@@ -0,0 +1,186 @@
+use std::cmp::{min, max};
+use std::collections::{HashSet, HashMap};
+
+////////////////////////////////////////////////////////////
+
+use structs::run_config::Config;
+use useful::Orientation;
+use prepare::reverse_complement;
+
+/*
+A naive solver for the same problem, used to test the real one. Every pair of input strings is aligned
+in every relative position, and each position is checked directly. This takes O(n^2) pairs of strings,
+each with O(l^2) positions for strings of length l. So it is only suitable for small inputs.
+Strings are expected to contain no N symbols.
+*/
+#[derive(Hash, PartialEq, Eq, Debug, Clone)]
+pub struct Overlap {
+    pub a_name : String,
+    pub b_name : String,
+    pub orientation : Orientation,
+    pub overhang_left_a : i32,
+    pub overhang_right_b : i32,
+    pub overlap_a : usize,
+    pub overlap_b : usize,
+    pub errors : u32,
+}
+
+// the canonical alignment of every overlap (see canonical)
+pub fn find_overlaps(reads : &[(String, Vec<u8>)], config : &Config) -> HashSet<Overlap> {
+    let alignments = find_alignments(reads, config);
+    canonical(&alignments, &alignments)
+}
+
+// every alignment of two strings within the error limit
+pub fn find_alignments(reads : &[(String, Vec<u8>)], config : &Config) -> HashSet<Overlap> {
+    let mut overlaps = HashSet::new();
+    for (i, &(ref a_name, ref a)) in reads.iter().enumerate() {
+        for &(ref b_name, ref b) in reads[i+1..].iter() {
+            // A is the string with the smaller name
+            let (a_name, a, b_name, b) = if a_name <= b_name {
+                (a_name, a, b_name, b)
+            } else {
+                (b_name, b, a_name, a)
+            };
+            pair_overlaps(a_name, a, b_name, b, Orientation::Normal, config, &mut overlaps);
+            if config.reversals {
+                let b_rev = reverse_complement(b);
+                pair_overlaps(a_name, a, b_name, &b_rev, Orientation::Reversed, config, &mut overlaps);
+            }
+        }
+    }
+    overlaps
+}
+
+/*
+a: [a1|a2|a3]
+b: [b1|b2|b3]
+with a2 and b2 aligned, and at most one of each (a1, b1) and (a3, b3) nonzero.
+Suffix-prefix overlaps have a3 == b1 == 0 or a1 == b3 == 0.
+Inclusions (if enabled) have a1 == a3 == 0 or b1 == b3 == 0.
+*/
+fn pair_overlaps(a_name : &str, a : &[u8], b_name : &str, b : &[u8], orientation : Orientation,
+                 config : &Config, overlaps : &mut HashSet<Overlap>) {
+    // (x1, x3) pairs with at most one of each of (a1, b1) and (a3, b3) nonzero, as signed overhangs
+    let (la, lb) = (a.len() as i32, b.len() as i32);
+    for left in -lb+1..la {
+        for right in -la+1..lb {
+            let (a1, b1) = if left >= 0 {(left, 0)} else {(0, -left)};
+            let (b3, a3) = if right >= 0 {(right, 0)} else {(0, -right)};
+            let (a2, b2) = (la - a1 - a3, lb - b1 - b3);
+            if a2 <= 0 || b2 <= 0 || (!config.edit_distance && a2 != b2) {
+                continue;
+            }
+            let suff_pref = (a3 == 0 && b1 == 0) || (a1 == 0 && b3 == 0);
+            let inclusion = (a1 == 0 && a3 == 0) || (b1 == 0 && b3 == 0);
+            if !(suff_pref || (config.inclusions && inclusion)) {
+                continue;
+            }
+            let longer = max(a2, b2) as usize;
+            if longer < config.thresh as usize {
+                continue;
+            }
+            let limit = config.max_errors_for(longer);
+            if (a2 - b2).abs() > limit {
+                continue;
+            }
+            let a_part = &a[a1 as usize..(la - a3) as usize];
+            let b_part = &b[b1 as usize..(lb - b3) as usize];
+            let errors = if config.edit_distance {
+                anchored_edit_distance(a_part, b_part)
+            } else {
+                a_part.iter().zip(b_part.iter()).filter(|&(x, y)| x != y).count() as u32
+            };
+            if errors as i32 <= limit {
+                overlaps.insert(Overlap {
+                    a_name : a_name.to_owned(),
+                    b_name : b_name.to_owned(),
+                    orientation : orientation.clone(),
+                    overhang_left_a : left,
+                    overhang_right_b : right,
+                    overlap_a : a2 as usize,
+                    overlap_b : b2 as usize,
+                    errors : errors,
+                });
+            }
+        }
+    }
+}
+
+// edit distance where the first characters and the last characters of both strings are aligned to one another
+fn anchored_edit_distance(a : &[u8], b : &[u8]) -> u32 {
+    let mismatch = |x : u8, y : u8| if x == y {0} else {1};
+    if a.len() == 1 || b.len() == 1 {
+        if a.len() != b.len() {
+            return u32::max_value();
+        }
+        return mismatch(a[0], b[0]);
+    }
+    let ends = mismatch(a[0], b[0]) + mismatch(a[a.len()-1], b[b.len()-1]);
+    let (a, b) = (&a[1..a.len()-1], &b[1..b.len()-1]);
+    // plain levenshtein distance of the inner parts
+    let mut row : Vec<u32> = (0..b.len() as u32 + 1).collect();
+    for i in 0..a.len() {
+        let mut next = vec![i as u32 + 1; b.len() + 1];
+        for j in 0..b.len() {
+            next[j+1] = min(min(row[j+1] + 1, next[j] + 1), row[j] + mismatch(a[i], b[j]));
+        }
+        row = next;
+    }
+    row[b.len()] + ends
+}
+
+/*
+With edit distance, one overlap has many alignments, whose ends are shifted against each other by indels.
+Which of these the solver reports, besides the best, depends on its filters.
+Alignments of the same strings in the same orientation belong to the same overlap if they share an end
+(overhang_left_a or overhang_right_b), directly or through other alignments of the overlap.
+Overlaps are formed from all the given alignments. Each overlap among those found is represented by its
+alignment with the fewest errors found, with ties broken by the overlap lengths and overhangs.
+So the solver agrees with the oracle exactly if it finds a best alignment of every overlap.
+With hamming distance, alignments sharing an end are the same, so every alignment is an overlap of its own.
+*/
+pub fn canonical(found : &HashSet<Overlap>, alignments : &HashSet<Overlap>) -> HashSet<Overlap> {
+    let alignments : Vec<&Overlap> = alignments.iter().collect();
+    let mut overlap_of : Vec<usize> = (0..alignments.len()).collect();
+    let mut by_end : HashMap<(&str, &str, &Orientation, bool, i32), usize> = HashMap::new();
+    for (i, a) in alignments.iter().enumerate() {
+        for &end in [(false, a.overhang_left_a), (true, a.overhang_right_b)].iter() {
+            let key = (&a.a_name[..], &a.b_name[..], &a.orientation, end.0, end.1);
+            match by_end.get(&key) {
+                Some(&j) => join(&mut overlap_of, i, j),
+                None => {by_end.insert(key, i);},
+            }
+        }
+    }
+    let rank = |a : &Overlap| (a.errors, (a.overlap_a as i32 - a.overlap_b as i32).abs(), a.overhang_left_a, a.overhang_right_b);
+    let mut fewest_errors : HashMap<usize, u32> = HashMap::new();
+    for (i, a) in alignments.iter().enumerate().filter(|&(_, a)| found.contains(a)) {
+        let errors = fewest_errors.entry(root(&overlap_of, i)).or_insert(a.errors);
+        *errors = min(*errors, a.errors);
+    }
+    let mut best : HashMap<usize, &Overlap> = HashMap::new();
+    for (i, &a) in alignments.iter().enumerate() {
+        let overlap = root(&overlap_of, i);
+        if fewest_errors.get(&overlap) != Some(&a.errors) {
+            continue;
+        }
+        let representative = best.entry(overlap).or_insert(a);
+        if rank(a) < rank(representative) {
+            *representative = a;
+        }
+    }
+    best.values().map(|&a| a.clone()).collect()
+}
+
+fn root(overlap_of : &[usize], mut i : usize) -> usize {
+    while overlap_of[i] != i {
+        i = overlap_of[i];
+    }
+    i
+}
+
+fn join(overlap_of : &mut [usize], i : usize, j : usize) {
+    let (i, j) = (root(overlap_of, i), root(overlap_of, j));
+    overlap_of[i] = j;
+}
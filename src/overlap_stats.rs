@@ -0,0 +1,203 @@
+use std::io;
+use std::fs::File;
+use std::fmt;
+use std::collections::HashMap;
+use std::cmp::max;
+use bio::io::fasta;
+
+////////////////////////////////////////////////////////////
+
+use tsv::Overlap;
+use useful::Orientation;
+
+/*
+Summarizes a set of overlaps (as written by a run) to sanity-check a dataset before assembling it.
+Reads are identified by name. The names of reads with no overlaps at all are only known if the fasta
+file of reads is given, so singletons are only reported then.
+Read ends are as given in the fasta file: for an I overlap, B's begin and end are swapped
+with respect to the reversed B in the overlap.
+*/
+#[derive(Debug)]
+pub struct OverlapStats {
+    pub reads : usize,
+    pub overlaps : usize,
+    pub containments : usize,
+    pub contained_reads : usize,
+    pub singletons : Option<usize>,
+    pub begin_degrees : Vec<usize>, // bin 0 counts read begins without dovetail overlaps, bin i+1 those with [2^i, 2^(i+1))
+    pub end_degrees : Vec<usize>,
+    pub components : Vec<usize>, // sizes of the connected components, largest first
+    pub overlap_lengths : Vec<usize>, // bin i counts overlaps with max(OLA, OLB) in [2^i, 2^(i+1))
+    pub errors : Vec<usize>, // number of overlaps with i errors
+}
+
+impl OverlapStats {
+    pub fn new(overlaps : &[Overlap], read_names : Option<Vec<String>>) -> OverlapStats {
+        let singletons_known = read_names.is_some();
+        let mut ids : HashMap<String, usize> = HashMap::new();
+        for name in read_names.unwrap_or(Vec::new()) {
+            let next = ids.len();
+            ids.entry(name).or_insert(next);
+        }
+        for o in overlaps.iter() {
+            for name in [&o.name_a, &o.name_b].iter() {
+                if !ids.contains_key(*name) {
+                    let next = ids.len();
+                    ids.insert((*name).clone(), next);
+                }
+            }
+        }
+        let reads = ids.len();
+
+        let mut begin_degree = vec![0; reads];
+        let mut end_degree = vec![0; reads];
+        let mut contained = vec![false; reads];
+        let mut components = UnionFind::new(reads);
+        let mut containments = 0;
+        let mut overlap_lengths = Vec::new();
+        let mut errors = Vec::new();
+        for o in overlaps.iter() {
+            let (a, b) = (ids[&o.name_a], ids[&o.name_b]);
+            components.union(a, b);
+            add_to_bin(&mut errors, o.errors as usize);
+            add_to_bin(&mut overlap_lengths, log2(max(1, max(o.overlap_a, o.overlap_b))));
+            let (oha, ohb) = (o.overhang_left_a, o.overhang_right_b);
+            if oha >= 0 && ohb <= 0 {
+                // B lies within A. if they span one another, B is the one counted
+                containments += 1;
+                contained[b] = true;
+                continue;
+            } else if oha <= 0 && ohb >= 0 {
+                containments += 1;
+                contained[a] = true;
+                continue;
+            }
+            // A then B: A's end meets (reversed) B's begin. B then A: the other way around
+            let a_then_b = oha > 0;
+            let b_begins = a_then_b == (o.orientation == Orientation::Normal);
+            if a_then_b {end_degree[a] += 1} else {begin_degree[a] += 1}
+            if b_begins {begin_degree[b] += 1} else {end_degree[b] += 1}
+        }
+
+        let mut sizes : HashMap<usize, usize> = HashMap::new();
+        for r in 0..reads {
+            *sizes.entry(components.find(r)).or_insert(0) += 1;
+        }
+        let mut component_sizes : Vec<usize> = sizes.values().cloned().collect();
+        component_sizes.sort_by(|x, y| y.cmp(x));
+
+        OverlapStats {
+            reads : reads,
+            overlaps : overlaps.len(),
+            containments : containments,
+            contained_reads : contained.iter().filter(|&&c| c).count(),
+            singletons : if singletons_known {Some(component_sizes.iter().filter(|&&s| s == 1).count())} else {None},
+            begin_degrees : degree_histogram(&begin_degree),
+            end_degrees : degree_histogram(&end_degree),
+            components : component_sizes,
+            overlap_lengths : overlap_lengths,
+            errors : errors,
+        }
+    }
+}
+
+// the names of the reads in a fasta file, in order
+pub fn read_names(path : &str) -> Result<Vec<String>, io::Error> {
+    let mut names = Vec::new();
+    for record in fasta::Reader::new(File::open(path)?).records() {
+        if let Some(name) = record?.id() {
+            names.push(name.to_owned());
+        }
+    }
+    Ok(names)
+}
+
+fn add_to_bin(bins : &mut Vec<usize>, bin : usize) {
+    if bins.len() <= bin {
+        bins.resize(bin + 1, 0);
+    }
+    bins[bin] += 1;
+}
+
+fn degree_histogram(degrees : &[usize]) -> Vec<usize> {
+    let mut bins = Vec::new();
+    for &d in degrees.iter() {
+        add_to_bin(&mut bins, if d == 0 {0} else {log2(d) + 1});
+    }
+    bins
+}
+
+fn log2(x : usize) -> usize {
+    (0usize.leading_zeros() - x.leading_zeros() - 1) as usize
+}
+
+// disjoint sets of reads, with path halving
+struct UnionFind {
+    parent : Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n : usize) -> UnionFind {
+        UnionFind{parent : (0..n).collect()}
+    }
+
+    fn find(&mut self, mut x : usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    fn union(&mut self, x : usize, y : usize) {
+        let (x, y) = (self.find(x), self.find(y));
+        self.parent[x] = y;
+    }
+}
+
+// bin i of a power-of-two histogram
+fn power_bin(i : usize) -> String {
+    let (lo, hi) = (1usize << i, (1usize << (i + 1)) - 1);
+    if lo == hi {lo.to_string()} else {format!("{}-{}", lo, hi)}
+}
+
+fn degree_bin(i : usize) -> String {
+    if i == 0 {"0".to_owned()} else {power_bin(i - 1)}
+}
+
+// bins with a count of zero are skipped
+fn write_histogram<F : Fn(usize) -> String>(f : &mut fmt::Formatter, bins : &[usize], label : F) -> fmt::Result {
+    for (i, &count) in bins.iter().enumerate() {
+        if count > 0 {
+            write!(f, "    {:<16}{:>10}\n", label(i), count)?;
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for OverlapStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OK {} reads with {} overlaps, of which {} are dovetails and {} containments.\n",
+               self.reads, self.overlaps, self.overlaps - self.containments, self.containments)?;
+        write!(f, "OK {} reads are contained in another.\n", self.contained_reads)?;
+        if let Some(singletons) = self.singletons {
+            write!(f, "OK {} reads overlap no other.\n", singletons)?;
+        }
+        let largest : Vec<String> = self.components.iter().take(5).map(|s| s.to_string()).collect();
+        write!(f, "OK {} connected components. largest : {}\n", self.components.len(), largest.join(", "))?;
+        let mut component_bins = Vec::new();
+        for &size in self.components.iter() {
+            add_to_bin(&mut component_bins, log2(size));
+        }
+        write!(f, "OK component sizes:\n")?;
+        write_histogram(f, &component_bins, power_bin)?;
+        write!(f, "OK dovetail overlaps at the begin of a read:\n")?;
+        write_histogram(f, &self.begin_degrees, degree_bin)?;
+        write!(f, "OK dovetail overlaps at the end of a read:\n")?;
+        write_histogram(f, &self.end_degrees, degree_bin)?;
+        write!(f, "OK overlap lengths:\n")?;
+        write_histogram(f, &self.overlap_lengths, power_bin)?;
+        write!(f, "OK errors per overlap:\n")?;
+        write_histogram(f, &self.errors, |i| i.to_string())
+    }
+}
@@ -17,6 +17,11 @@ the "maps" contains most of the constant information for the run
 > some convenient functions ie: get &str (in the text)
 */
 pub fn read_and_prepare(filename : &str, config : &Config) -> Result<(Maps), io::Error> {
+    read_maps(filename, config.reversals, config.n_alphabet)
+}
+
+// as read_and_prepare, for when there is no config (i.e. to post-process the output of a run)
+pub fn read_maps(filename : &str, reversals : bool, n_alphabet : bool) -> Result<Maps, io::Error> {
     let mut text : Vec<u8> = Vec::new();
     let mut id2name_vec : Vec<String> = Vec::new();
     let mut id2index_bdmap : BidirMap<usize, usize> = BidirMap::new();
@@ -31,7 +36,7 @@ pub fn read_and_prepare(filename : &str, config : &Config) -> Result<(Maps), io:
             let id = id2name_vec.len();
             let name = name.to_owned();
             let mut str_vec = record.seq().to_vec();
-            if !config.n_alphabet{
+            if !n_alphabet{
                 let before_len = str_vec.len();
                 str_vec.retain(|c|*c != ('N' as u8));
                 if str_vec.len() < before_len{
@@ -45,7 +50,7 @@ pub fn read_and_prepare(filename : &str, config : &Config) -> Result<(Maps), io:
             text.extend(str_vec.clone());
             id2name_vec.push(name.clone());
 
-            if config.reversals{
+            if reversals{
                 let id = id2name_vec.len();
                 str_vec.reverse();
                 for i in 0..str_vec.len(){
@@ -81,7 +86,11 @@ pub fn read_and_prepare(filename : &str, config : &Config) -> Result<(Maps), io:
     Ok(maps)
 }
 
-fn complement_u8(x : u8) -> u8 {
+pub fn reverse_complement(s : &[u8]) -> Vec<u8> {
+    s.iter().rev().map(|&c| complement_u8(c)).collect()
+}
+
+pub fn complement_u8(x : u8) -> u8 {
     match x{
         b'A' => b'T',
         b'C' => b'G',
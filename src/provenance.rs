@@ -0,0 +1,59 @@
+use std::io;
+use std::io::Read;
+use std::fs::File;
+use std::time::{SystemTime, UNIX_EPOCH};
+use md5;
+
+////////////////////////////////////////////////////////////
+
+use structs::run_config::{Config, Maps};
+use modes::Mode;
+
+/*
+Describes how a run was produced, for --provenance. The lines are written as comments (starting with '#')
+at the top of the output and of the GFA of --gfa, and are carried along by filter, convert and graph
+where their output has comments. Each line is 'KEY: VALUE', without the '#'.
+*/
+pub fn provenance(config : &Config, maps : &Maps, mode : &Mode) -> Result<Vec<String>, io::Error> {
+    let ids_per_read = if config.reversals {2} else {1};
+    let reads = maps.num_ids() / ids_per_read;
+    let bases : usize = (0..reads).map(|r| maps.get_length(r * ids_per_read)).sum();
+    Ok(vec![
+        format!("version: {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        format!("run: {}", utc_timestamp(SystemTime::now())),
+        format!("input: {} md5 {} reads {} bases {}", config.input, md5_of(&config.input)?, reads, bases),
+        format!("mode: {}", mode),
+        format!("config: {:?}", config),
+    ])
+}
+
+fn md5_of(path : &str) -> Result<String, io::Error> {
+    let mut f = File::open(path)?;
+    let mut context = md5::Context::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        match f.read(&mut buf)? {
+            0 => break,
+            n => context.consume(&buf[..n]),
+        }
+    }
+    Ok(format!("{:x}", context.compute()))
+}
+
+// ISO 8601 in UTC, ie: '2017-06-01T12:00:00Z'
+pub fn utc_timestamp(time : SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, secs_of_day) = ((secs / 86400) as i64, secs % 86400);
+    // the civil date of a number of days since 1970-01-01, by Howard Hinnant's civil_from_days
+    let z = days + 719468;
+    let era = z / 146097;
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 {mp + 3} else {mp - 9};
+    let year = year_of_era + era * 400 + if month <= 2 {1} else {0};
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day,
+            secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60)
+}
@@ -2,18 +2,21 @@ use bio::data_structures::fmindex::Interval;
 use bio::data_structures::suffix_array::RawSuffixArray;
 use bio::data_structures::fmindex::FMIndexable;
 
-extern crate stacker; // for avoiding stackoverflow
-
 use std;
-use std::collections::HashSet;
+use std::collections::{HashSet, HashMap};
+use std::collections::hash_map::Entry;
 use std::cmp::{min,max};
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::Ordering;
 
 ////////////////////////////////////////////////////////////
 
 use structs::run_config::{Config, Maps};
 use structs::solutions::{Candidate};
+use structs::run_stats::Counters;
 use useful::companion_id;
-use modes::Mode;
+use modes::{Mode, Search};
+use bidirectional::{BiFMIndex, BiInterval};
 
 pub static READ_ERR : u8 = b'N';
 
@@ -44,6 +47,7 @@ pub trait GeneratesCandidates : FMIndexable {
                            id_a : usize,
                            sa : &RawSuffixArray,
                            mode : &Mode,
+                           counters : &Counters,
                             ) -> HashSet<Candidate> {
 
         let mut candidate_set: HashSet<Candidate> = HashSet::new();
@@ -60,7 +64,7 @@ pub trait GeneratesCandidates : FMIndexable {
 
         // necessary data for the search which remains constant for the entire pattern
         let max_b_len = if config.edit_distance {
-            (patt_len as f32 / (1.0-config.err_rate)).floor() as usize
+            config.longest_b_overlap(patt_len)
         } else {
             patt_len
         };
@@ -72,14 +76,19 @@ pub trait GeneratesCandidates : FMIndexable {
         }
         let p_cns = PatternConstants{
             pattern: pattern,
-            hard_error_cap : (max_b_len as f32 * config.err_rate).floor() as i32,
+            hard_error_cap : config.max_errors_for(max_b_len),
             config : config,
             maps : maps,
             block_id_lookup : &block_id_lookup,
+            block_lengths : &block_lengths,
             sa : sa,
             id_a : id_a,
             patt_blocks : patt_blocks,
             mode : mode,
+            nodes : Cell::new(0),
+            generated : RefCell::new(Vec::new()),
+            reported : RefCell::new(HashMap::new()),
+            skipped : Cell::new(0),
         };
 
         /*
@@ -90,48 +99,81 @@ pub trait GeneratesCandidates : FMIndexable {
         [2][1]
         [2]
         */
+        let mut filter_nodes = Vec::new();
         for (first_block_id, block_len) in block_lengths.iter().enumerate() {
             let suff_blocks = patt_blocks - first_block_id as i32; //first_block_id == blind_blocks
             if suff_blocks < p_cns.mode.get_fewest_suff_blocks() {
                 break;
             }
 
+            let nodes_before = p_cns.nodes.get();
             let s_cns = SuffixConstants {
                 blind_blocks: first_block_id as i32,
                 blind_a_chars: patt_len - p_i as usize - 1,
-                generous_blind_chars : ((patt_len - p_i as usize - 1) as f32 / (1.0-config.err_rate)).floor() as usize,
+                generous_blind_chars : config.longest_b_overlap(patt_len - p_i as usize - 1),
+                block_bounds : Vec::new(),
             };
 
-            //This begins the search and represents a single "query" for a single pattern filter
-            self.recurse_candidates(
-                &mut candidate_set, &p_cns, &s_cns, 0, p_i,
-                LastOperation::Initial, 0, 0,
-                &full_interval,
-            );
+            match p_cns.mode.get_searches(suff_blocks, patt_blocks) {
+                //the mode splits this filter into several bidirectional searches
+                Some(searches) => self.scheme_candidates(&mut candidate_set, &p_cns, &s_cns, p_i, &searches),
+
+                //This begins the search and represents a single "query" for a single pattern filter
+                None => self.filter_candidates(&mut candidate_set, &p_cns, &s_cns, SearchNode{
+                    errors : 0,
+                    p_i : p_i,
+                    last_operation : LastOperation::Initial,
+                    a_match_len : 0,
+                    b_match_len : 0,
+                    interval : full_interval,
+                }),
+            }
+
+            filter_nodes.push(p_cns.nodes.get() - nodes_before);
 
             // the filters begin as the entire pattern, and gradually get shorter.
             p_i -= *block_len;
 
         }
+        counters.search_nodes.fetch_add(p_cns.nodes.get(), Ordering::Relaxed);
+        let generated = p_cns.generated.borrow();
+        counters.candidates_generated.fetch_add(generated.iter().sum(), Ordering::Relaxed);
+        counters.add_tallies(&filter_nodes, &generated);
+        counters.intervals_skipped.fetch_add(p_cns.skipped.get(), Ordering::Relaxed);
         candidate_set
     }
 
     /*
     This conceptually corresponds to the search for one FILTER of the candidate.
-    The call branches recursively as specified by the functions used for the algorithm mode.
-    Various information that changes with each iteration is stored on the call stack directly.
+    The search tree branches as specified by the functions used for the algorithm mode.
+    It is walked depth-first using an explicit stack of nodes rather than recursion, so the depth of the search
+    (ie: the length of the pattern) is not limited by the size of the call stack. As each node pushes at most
+    2*|alphabet|+1 children, the stack holds O(pattern length * alphabet size) nodes at any time.
+    */
+    fn filter_candidates(&self,
+                         cand_set : &mut HashSet<Candidate>,
+                         p_cns : &PatternConstants,
+                         s_cns : &SuffixConstants,
+                         start : SearchNode<Interval>,
+                         ){
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            self.expand_node(cand_set, p_cns, s_cns, node, &mut stack);
+        }
+    }
+
+    /*
+    Visits one node of a filter's search tree, generating its candidates and pushing its children to the stack.
     */
-    fn recurse_candidates(&self,
-                          cand_set : &mut HashSet<Candidate>,
-                          p_cns : &PatternConstants,
-                          s_cns : &SuffixConstants,
-                          errors : i32,
-                          p_i : i32,
-                          last_operation : LastOperation,
-                          a_match_len : usize,
-                          b_match_len : usize,
-                          match_interval : &Interval,
-                          ){
+    fn expand_node(&self,
+                   cand_set : &mut HashSet<Candidate>,
+                   p_cns : &PatternConstants,
+                   s_cns : &SuffixConstants,
+                   node : SearchNode<Interval>,
+                   stack : &mut Vec<SearchNode<Interval>>,
+                   ){
+        let SearchNode{errors, p_i, last_operation, a_match_len, b_match_len, interval : match_interval} = node;
+        p_cns.nodes.set(p_cns.nodes.get() + 1);
         if match_interval.lower > match_interval.upper{
             // range is inclusive on both ends within the walk.
             // empty range -> prune branch
@@ -144,9 +186,20 @@ pub trait GeneratesCandidates : FMIndexable {
             Some(x) => x - s_cns.blind_blocks,
             None    => p_cns.patt_blocks - s_cns.blind_blocks,
         };
+        if completed_blocks > 0 {
+            if let Some(&(lower, _)) = s_cns.block_bounds.get((completed_blocks - 1) as usize){
+                if errors < lower {
+                    // too few errors for this search of the scheme. another search covers this branch
+                    return
+                }
+            }
+        }
         //look up how many errors are allowed from the filter module
-        let permitted_errors : i32 = min(p_cns.hard_error_cap,
+        let mut permitted_errors : i32 = min(p_cns.hard_error_cap,
                                          p_cns.mode.filter_func(completed_blocks, p_cns.patt_blocks, s_cns.blind_blocks));
+        if let Some(&(_, upper)) = s_cns.block_bounds.get(completed_blocks as usize){
+            permitted_errors = min(permitted_errors, upper);
+        }
 
         //Design decision: if the lengths of A and B differ, we are generous with the size for lookups
 
@@ -161,9 +214,11 @@ pub trait GeneratesCandidates : FMIndexable {
                 lower : less + if match_interval.lower > 0 { self.occ(match_interval.lower - 1, a) } else { 0 },
                 upper : less + self.occ(match_interval.upper, a),
             }; //final interval must have exclusive end
-            let positions = dollar_interval.occ(p_cns.sa);
-            if positions.len() > 0{
-                add_candidates_from_positions(positions, cand_set, p_cns, s_cns, a_match_len, b_match_len, false);
+            if p_cns.first_report(&dollar_interval, a_match_len + s_cns.blind_a_chars, b_match_len, false) {
+                let positions = dollar_interval.occ(p_cns.sa);
+                if positions.len() > 0{
+                    add_candidates_from_positions(positions, cand_set, p_cns, s_cns, errors, a_match_len, b_match_len, false);
+                }
             }
         }
 
@@ -176,9 +231,11 @@ pub trait GeneratesCandidates : FMIndexable {
                     lower : match_interval.lower,
                     upper : match_interval.upper + 1,
                 }; // final interval must have exclusive end
-                let positions = inclusion_interval.occ(p_cns.sa);
-                if positions.len() > 0{
-                    add_candidates_from_positions(positions, cand_set, p_cns, s_cns, a_match_len, b_match_len, true);
+                if p_cns.first_report(&inclusion_interval, a_match_len + s_cns.blind_a_chars, b_match_len, true) {
+                    let positions = inclusion_interval.occ(p_cns.sa);
+                    if positions.len() > 0{
+                        add_candidates_from_positions(positions, cand_set, p_cns, s_cns, errors, a_match_len, b_match_len, true);
+                    }
                 }
             }
             return;
@@ -195,63 +252,71 @@ pub trait GeneratesCandidates : FMIndexable {
             };
             let recurse_errors =  if p_char == a && a != READ_ERR {errors} else {errors + 1};
             if recurse_errors <= permitted_errors {
-                // recursively explore SUBSTITUTION cases (both hamming and levenshtein)
-                stacker::maybe_grow(32 * 1024, 1024 * 1024, || {
-                    // guaranteed to have at least 32K of stack
-                    self.recurse_candidates(cand_set,
-                                            p_cns,
-                                            s_cns,
-                                            recurse_errors,
-                                            p_i-1,  //step left
-                                            LastOperation::Substitution,
-                                            a_match_len + 1,
-                                            b_match_len + 1,
-                                            &next_interval,
-                                            );
+                // explore SUBSTITUTION cases (both hamming and levenshtein)
+                stack.push(SearchNode{
+                    errors : recurse_errors,
+                    p_i : p_i-1,  //step left
+                    last_operation : LastOperation::Substitution,
+                    a_match_len : a_match_len + 1,
+                    b_match_len : b_match_len + 1,
+                    interval : next_interval,
                 });
             }
             if (errors < permitted_errors) && p_cns.config.edit_distance && last_operation.allows_insertion() {
-                if p_char != a{
-                    // recursively explore INSERTION cases (if levenshtein)
-                    stacker::maybe_grow(32 * 1024, 1024 * 1024, || {
-                        // guaranteed to have at least 32K of stack
-                        self.recurse_candidates(cand_set,
-                                                p_cns,
-                                                s_cns,
-                                                errors + 1, //always induces an error
-                                                p_i,        //don't step left
-                                                LastOperation::Insertion,
-                                                a_match_len,//the pattern string doesn't grow
-                                                b_match_len + 1,
-                                                &next_interval,
-                                                );
+                // inserting the pattern's char is redundant with matching it and inserting after.
+                // but nothing is inserted after the pattern's first char
+                if p_char != a || p_i == 0 {
+                    // explore INSERTION cases (if levenshtein)
+                    stack.push(SearchNode{
+                        errors : errors + 1,            //always induces an error
+                        p_i : p_i,                      //don't step left
+                        last_operation : LastOperation::Insertion,
+                        a_match_len : a_match_len,      //the pattern string doesn't grow
+                        b_match_len : b_match_len + 1,
+                        interval : next_interval,
                     });
                 }
             }
         }
 
         if p_cns.config.edit_distance && errors < permitted_errors && !pattern_finished{
-            // recursively explore DELETION cases (if levenshtein) and have at least 1 spare pattern char to jump over
+            // explore DELETION cases (if levenshtein) and have at least 1 spare pattern char to jump over
             if last_operation.allows_deletion(){
-
-//              let next_debug = format!("{}{}", '_', debug);
-
-                stacker::maybe_grow(32 * 1024, 1024 * 1024, || {
-                    // guaranteed to have at least 32K of stack
-                    self.recurse_candidates(cand_set,
-                                            p_cns,
-                                            s_cns,
-                                            errors + 1,
-                                            p_i - 1,         //one step without matching
-                                            LastOperation::Deletion,
-                                            a_match_len + 1,
-                                            b_match_len,     //the matched string doesn't grow
-                                            &match_interval, //stays unchanged
-                                            );
+                stack.push(SearchNode{
+                    errors : errors + 1,
+                    p_i : p_i - 1,                  //one step without matching
+                    last_operation : LastOperation::Deletion,
+                    a_match_len : a_match_len + 1,
+                    b_match_len : b_match_len,      //the matched string doesn't grow
+                    interval : match_interval,      //stays unchanged
                 });
             }
         }
     }
+
+    /*
+    Runs the searches of a bidirectional search scheme for the filter beginning at p_i.
+    Only a bidirectional index (see bidirectional.rs) can extend matches in the other direction.
+    */
+    #[allow(unused_variables)]
+    fn scheme_candidates(&self,
+                         cand_set : &mut HashSet<Candidate>,
+                         p_cns : &PatternConstants,
+                         s_cns : &SuffixConstants,
+                         p_i : i32,
+                         searches : &[Search]){
+        panic!("Mode '{}' uses search schemes, which require a bidirectional index.", p_cns.mode);
+    }
+}
+
+// the state of the search at one node of a filter's search tree
+pub struct SearchNode<I>{
+    errors : i32,
+    p_i : i32,                       //the NEXT char of the pattern to be matched
+    last_operation : LastOperation,
+    a_match_len : usize,
+    b_match_len : usize,
+    interval : I,                    //the matches in the index so far
 }
 
 #[derive(PartialEq, Copy, Clone)]
@@ -290,7 +355,7 @@ locations to generate candidates. For each, add a new candidate to cand_set
 #[inline]
 fn add_candidates_from_positions(positions : Vec<usize>,
                                  cand_set : &mut HashSet<Candidate>, p_cns : &PatternConstants,
-                                 s_cns : &SuffixConstants, a_match_len : usize,
+                                 s_cns : &SuffixConstants, errors : i32, a_match_len : usize,
                                  b_match_len : usize, inclusion : bool){
     for mut position in positions {
         if !inclusion{
@@ -352,10 +417,8 @@ fn add_candidates_from_positions(positions : Vec<usize>,
             // b_overlap_len is unknown, but it has upper and lower bounds as determined by the
             // length of b, the error rate etc.
             (
-                max((a2 as f32 * (1.0-p_cns.config.err_rate)).ceil() as usize,
-                    b_match_len),
-                min((a2 as f32 / (1.0-p_cns.config.err_rate)).floor() as usize,
-                    b_len),
+                max(p_cns.config.shortest_b_overlap(a2), b_match_len),
+                min(p_cns.config.longest_b_overlap(a2), b_len),
             )
         };
         let possible_b2s = (min_b2)..(max_b2 + 1);
@@ -378,6 +441,13 @@ fn add_candidates_from_positions(positions : Vec<usize>,
                 overlap_b: b2,
                 overhang_left_a: a1 - b1,
             };
+            {
+                let mut generated = p_cns.generated.borrow_mut();
+                if generated.len() <= errors as usize {
+                    generated.resize(errors as usize + 1, 0);
+                }
+                generated[errors as usize] += 1;
+            }
             if !cand_set.contains(&c){
                 cand_set.insert(c);
             }
@@ -393,18 +463,62 @@ pub struct SuffixConstants{
 
     //"generous X" == max(X_of_A, X_of_b)
     generous_blind_chars : usize,
+
+    //(lower, upper) error bounds once the block of the filter with that id is completed, by block id.
+    //only used by searches of a search scheme
+    block_bounds : Vec<(i32, i32)>,
 }
 
 pub struct PatternConstants<'a>{
     config : &'a Config,
     maps : &'a Maps,
     block_id_lookup : &'a Vec<i32>,
+    block_lengths : &'a Vec<i32>,
     sa : &'a RawSuffixArray,
     pattern: &'a [u8],
     id_a : usize,
     hard_error_cap : i32,
     patt_blocks : i32,
     mode : &'a Mode,
+
+    //tallies for this pattern, added to the run's counters once the pattern is done
+    nodes : Cell<usize>,
+    generated : RefCell<Vec<usize>>, //indexed by the errors of the search when the candidate was generated
+
+    //(interval lower, interval upper, a overlap, inclusion) --> shortest b match reported for it
+    reported : RefCell<HashMap<(usize, usize, usize, bool), usize>>,
+    skipped : Cell<usize>,
+}
+
+impl<'a> PatternConstants<'a>{
+    /*
+    Different filters (and different paths through the same filter's search) often match the same B strings
+    at the same overlap length of A. They then find the same suffix array interval, which would produce the same candidates.
+    Returns false if the interval was already reported for this overlap of A with a B match at most as long.
+    The candidates it produced then include all of those the new report would produce.
+    Hamming distance candidates don't depend on the length of the B match.
+    */
+    fn first_report(&self, interval : &Interval, a2 : usize, b_match_len : usize, inclusion : bool) -> bool {
+        if interval.lower >= interval.upper {
+            //empty
+            return false;
+        }
+        let b_bound = if self.config.edit_distance {b_match_len} else {0};
+        match self.reported.borrow_mut().entry((interval.lower, interval.upper, a2, inclusion)) {
+            Entry::Occupied(ref mut e) if *e.get() <= b_bound => {
+                self.skipped.set(self.skipped.get() + 1);
+                false
+            },
+            Entry::Occupied(mut e) => {
+                e.insert(b_bound);
+                true
+            },
+            Entry::Vacant(e) => {
+                e.insert(b_bound);
+                true
+            },
+        }
+    }
 }
 
 
@@ -420,3 +534,168 @@ pub fn get_block_id_lookup(block_lengths : &[i32]) -> Vec<i32>{
     lookup.reverse();
     lookup
 }
+
+
+/*
+The searches of a search scheme for a single filter, which begins at p_i and spans blocks
+[s_cns.blind_blocks, patt_blocks) of the pattern. Each search:
+1. matches its start block exactly, extending the match LEFTWARDS (conceptually forwards) from nothing
+2. extends the match RIGHTWARDS over the preceding blocks of the filter (see extend_rightwards)
+3. continues LEFTWARDS from the start block to the end of the pattern like any other filter search.
+   Candidates are only generated in this last step.
+*/
+pub fn scheme_candidates<'a, F, R>(index : &BiFMIndex<'a, F, R>,
+                                   cand_set : &mut HashSet<Candidate>,
+                                   p_cns : &PatternConstants,
+                                   s_cns : &SuffixConstants,
+                                   p_i : i32,
+                                   searches : &[Search])
+        where F : 'a + FMIndexable, R : 'a + FMIndexable {
+    for search in searches {
+        let start_block = s_cns.blind_blocks + search.start_block();
+        let right_end = p_cns.pattern.len() as i32 - 1 - p_cns.block_lengths[..start_block as usize].iter().sum::<i32>();
+        let left_end = right_end - p_cns.block_lengths[start_block as usize] + 1;
+
+        let mut interval = index.full_interval();
+        for i in (left_end..right_end+1).rev() {
+            let p_char = p_cns.pattern[i as usize];
+            if p_char == READ_ERR {
+                //N symbols are always errors
+                interval.size = 0;
+                break;
+            }
+            interval = index.extend_left(&interval, p_char);
+            if interval.size == 0 {
+                break;
+            }
+        }
+        if interval.size == 0 {
+            continue;
+        }
+        let search_cns = SuffixConstants {
+            blind_blocks : s_cns.blind_blocks,
+            blind_a_chars : s_cns.blind_a_chars,
+            generous_blind_chars : s_cns.generous_blind_chars,
+            block_bounds : bounds_by_block(search),
+        };
+        let matched = (right_end - left_end + 1) as usize;
+        let ends = SearchEnds {
+            filter_end : p_i,
+            start_left_end : left_end,
+        };
+        extend_rightwards(index, cand_set, p_cns, &search_cns, search, &ends, SearchNode{
+            errors : 0,
+            p_i : right_end + 1,
+            last_operation : LastOperation::Substitution,
+            a_match_len : matched,
+            b_match_len : matched,
+            interval : interval,
+        });
+    }
+}
+
+// the bounds of each step of the search, by the id of the block that step completes
+fn bounds_by_block(search : &Search) -> Vec<(i32, i32)> {
+    let mut bounds = vec![(0, 0); search.order.len()];
+    for (step, &block) in search.order.iter().enumerate() {
+        bounds[block as usize] = (search.lower[step], search.upper[step]);
+    }
+    bounds
+}
+
+// in-silico positions delimiting the current search
+struct SearchEnds {
+    filter_end : i32,     //rightmost char of the filter
+    start_left_end : i32, //leftmost char of the start block
+}
+
+/*
+Step 2 of a search in a search scheme. Like filter_candidates, but extends the match one char
+to the RIGHT at a time using the reverse index, until the filter's rightmost char (filter_end) is matched.
+Insertions between two blocks count towards the block on the left, as they would in filter_candidates.
+So the bounds for a completed block are only checked when the first char of the next block is matched.
+As in filter_candidates, the search tree is walked with an explicit stack.
+*/
+fn extend_rightwards<'a, F, R>(index : &BiFMIndex<'a, F, R>,
+                               cand_set : &mut HashSet<Candidate>,
+                               p_cns : &PatternConstants,
+                               s_cns : &SuffixConstants,
+                               search : &Search,
+                               ends : &SearchEnds,
+                               start : SearchNode<BiInterval>)
+        where F : 'a + FMIndexable, R : 'a + FMIndexable {
+    let start_block = search.start_block();
+    let block_step = |i : i32| (start_block - (p_cns.block_id_lookup[i as usize] - s_cns.blind_blocks)) as usize;
+    let mut stack = vec![start];
+    while let Some(node) = stack.pop() {
+        let SearchNode{errors, p_i, last_operation, a_match_len, b_match_len, interval} = node;
+        p_cns.nodes.set(p_cns.nodes.get() + 1);
+        let prev_step = block_step(p_i - 1);
+        if p_i > ends.filter_end {
+            // all preceding blocks matched. continue with the succeeding blocks.
+            if errors < search.lower[prev_step] || errors > search.upper[prev_step] {
+                continue;
+            }
+            index.filter_candidates(cand_set, p_cns, s_cns, SearchNode{
+                errors : errors,
+                p_i : ends.start_left_end - 1,
+                last_operation : LastOperation::Substitution,
+                a_match_len : a_match_len,
+                b_match_len : b_match_len,
+                interval : interval.forward(),
+            });
+            continue;
+        }
+        let step = block_step(p_i);
+        if step != prev_step && (errors < search.lower[prev_step] || errors > search.upper[prev_step]) {
+            // the previous block is completed with the wrong number of errors
+            continue;
+        }
+        let permitted_errors = min(p_cns.hard_error_cap, search.upper[step]);
+        let insertion_permitted_errors = min(p_cns.hard_error_cap, search.upper[prev_step]);
+
+        let p_char = p_cns.pattern[p_i as usize];
+        for &a in p_cns.config.alphabet() {
+            let next_interval = index.extend_right(&interval, a);
+            if next_interval.size == 0 {
+                continue;
+            }
+            let recurse_errors =  if p_char == a && a != READ_ERR {errors} else {errors + 1};
+            if recurse_errors <= permitted_errors {
+                // SUBSTITUTION cases (both hamming and levenshtein)
+                stack.push(SearchNode{
+                    errors : recurse_errors,
+                    p_i : p_i + 1,                  //step right
+                    last_operation : LastOperation::Substitution,
+                    a_match_len : a_match_len + 1,
+                    b_match_len : b_match_len + 1,
+                    interval : next_interval,
+                });
+            }
+            if p_cns.config.edit_distance && errors < insertion_permitted_errors
+                    && last_operation.allows_insertion() && p_char != a {
+                // INSERTION cases (if levenshtein)
+                stack.push(SearchNode{
+                    errors : errors + 1,
+                    p_i : p_i,                      //don't step right
+                    last_operation : LastOperation::Insertion,
+                    a_match_len : a_match_len,      //the pattern string doesn't grow
+                    b_match_len : b_match_len + 1,
+                    interval : next_interval,
+                });
+            }
+        }
+        if p_cns.config.edit_distance && errors < permitted_errors
+                && last_operation.allows_deletion() && p_i < ends.filter_end {
+            // DELETION cases (if levenshtein). As in filter_candidates, the filter's first char is never deleted
+            stack.push(SearchNode{
+                errors : errors + 1,
+                p_i : p_i + 1,                      //one step without matching
+                last_operation : LastOperation::Deletion,
+                a_match_len : a_match_len + 1,
+                b_match_len : b_match_len,          //the matched string doesn't grow
+                interval : interval,
+            });
+        }
+    }
+}
@@ -1,74 +1,480 @@
 use num_cpus;
-use structs::run_config::Config;
-use modes::{IsMode, Mode};
+use structs::run_config::{Config, Scoring};
+use modes::Mode;
 use modes;
+use lossless::CheckRanges;
+use simulate::{SimConfig, LengthDist};
 use std::cmp::{min, max};
+use std::process;
+use clap::{App, SubCommand, Error, ErrorKind, ArgMatches};
+use benchmark::BenchConfig;
+use graph::{GraphConfig, DEFAULT_FUZZ};
+use convert::{ConvertConfig, Format};
+use filter::{FilterConfig, Expr};
+use index_stats::IndexConfig;
+use config_file::RunFile;
+
+static DEFAULT_SORT_MEMORY : usize = 1024 * 1024 * 1024;
+
+// what the user asked the program to do
+pub enum Task {
+    Overlaps(Mode, Config),
+    VerifyMode(Mode, CheckRanges),
+    Simulate(SimConfig),
+    Benchmark(BenchConfig),
+    Stats(String, Option<String>), // the overlaps, and optionally the fasta file of reads
+    Convert(ConvertConfig),
+    Filter(FilterConfig),
+    Index(Mode, IndexConfig),
+    Graph(String, String, bool, GraphConfig), // the overlaps, the fasta file of reads, whether N is kept in them
+}
 
 /*
 Using Clap, builds a config struct that contains all the user's input
+Finding overlaps is the default task, so its arguments are accepted without a subcommand too.
 */
-pub fn parse_run_args() -> (Box<IsMode>, Config) {
-    let matches = clap_app!(ASPOPsolver =>
+pub fn parse_run_args() -> Task {
+    let app = clap_app!(ASPOPsolver =>
         (version: "1.0")
         (author: "Christopher Esterhuyse <christopher.esterhuyse@gmail.com>")
         (about: "Finds approximate suffix prefix overlaps from a given fasta file")
 
-        (@arg IN_PATH: +required +takes_value "Path to the input fasta file")
-        (@arg OUT_PATH: +required +takes_value "Path of desired output file")
-        (@arg ERR_RATE: +required +takes_value "The max rate of errors in an overlap")
-        (@arg THRESH: +required +takes_value "Shortest allowed length of an overlap")
-
-        (@arg worker_threads: -w --worker_threads +takes_value "Number of worker threads used. Defaults to number of logical cpu cores")
-        (@arg mode: -m --mode +takes_value "Uses the filtering scheme mode given options {valimaki, kucherov}. Modes can also be supplied string arguments i.e. 'kucherov_2'. (Default : kucherov_2")
+        (@setting SubcommandsNegateReqs)
+        (@subcommand verify =>
+            (about: "Checks that a mode finds every valid (hamming distance) overlap for the given settings")
+            (@arg mode: -m --mode +takes_value "The mode to check, as for finding overlaps. (Default : kucherov_2)")
+            (@arg lengths: -l --lengths +takes_value "Range of pattern lengths 'MIN-MAX'. (Default : 10-80)")
+            (@arg err_rates: -e --err_rates +takes_value "Comma-separated error rates. (Default : 0.01,0.02,0.05,0.1)")
+            (@arg threshes: -t --threshes +takes_value "Range of thresholds 'MIN-MAX'. (Default : 5-25)")
+        )
+        (@subcommand simulate =>
+            (about: "Samples reads from a random (or given) genome. Writes them as fasta, and their true overlaps in the output format")
+            (@arg OUT_PATH: +required "Path of the fasta file of reads to write")
+            (@arg TRUTH_PATH: +required "Path of the file of true overlaps to write")
+            (@arg genome: -g --genome +takes_value "Fasta file of the genome to sample reads from. Each record is a separate sequence. (Default : a random genome)")
+            (@arg genome_len: --genome_len +takes_value "Length of the random genome. (Default : 10000)")
+            (@arg reads: -n --reads +takes_value "Number of reads. (Default : 1000)")
+            (@arg lengths: -l --lengths +takes_value "Read lengths as 'LEN', uniformly in 'MIN-MAX' or normally distributed 'MEAN~SD'. (Default : 100-150)")
+            (@arg sub_rate: --sub_rate +takes_value "Probability of substituting a genome symbol. (Default : 0.01)")
+            (@arg ins_rate: --ins_rate +takes_value "Probability of inserting a random symbol after a genome symbol. (Default : 0)")
+            (@arg del_rate: --del_rate +takes_value "Probability of deleting a genome symbol. (Default : 0)")
+            (@arg n_rate: --n_rate +takes_value "Probability of replacing a genome symbol with N. (Default : 0)")
+            (@arg rev_rate: --rev_rate +takes_value "Probability of reverse-complementing a read. (Default : 0.5)")
+            (@arg thresh: -t --thresh +takes_value "Shortest true overlap written. (Default : 1)")
+            (@arg seed: --seed +takes_value "Seed of the random number generator. (Default : 0)")
+        )
+        (@subcommand stats =>
+            (about: "Summarizes overlaps written by a run (or any file in the output format): containments, dovetail overlaps per read end, connected components, overlap lengths and errors")
+            (@arg OVERLAPS: +required "Path of the overlaps to summarize")
+            (@arg reads: -r --reads +takes_value "The fasta file of reads the overlaps are of, so that reads without overlaps are counted too")
+        )
+        (@subcommand convert =>
+            (about: "Converts overlaps written by a run (or in another format) between TSV (the output format), PAF, GFA, ASQG and MHAP")
+            (@arg IN_PATH: +required "Path of the overlaps to convert")
+            (@arg OUT_PATH: +required "Path of the converted overlaps to write")
+            (@arg reads: -r --reads +takes_value +required "The fasta file of reads the overlaps are of")
+            (@arg from: --from +takes_value "The format of the input: tsv, paf, gfa, asqg or mhap. (Default : by its extension)")
+            (@arg to: --to +takes_value "The format of the output: tsv, paf, gfa, asqg or mhap. (Default : by its extension)")
+            (@arg no_n: -n --no_n "Removes N symbols from the reads, as finding overlaps with -n does. Needed for the overlaps of such a run")
+        )
+        (@subcommand filter =>
+            (about: "Keeps the overlaps written by a run that satisfy the given conditions. The output is in the same format")
+            (@arg IN_PATH: +required "Path of the overlaps to filter")
+            (@arg OUT_PATH: +required "Path of the overlaps to keep")
+            (@arg expression: -x --expression +takes_value "Keeps the overlaps for which this is true, i.e. 'OLA >= 500 && K/OLA < 0.05 && O == \"N\"'. Columns are named as in the header line. See the README")
+            (@arg max_per_read: --max_per_read +takes_value "Keeps at most this many overlaps per read, preferring the longest")
+            (@arg keep_reads: --keep_reads +takes_value "File of read names, one per line. Keeps only the overlaps between these reads")
+            (@arg drop_reads: --drop_reads +takes_value "File of read names, one per line. Drops the overlaps of these reads")
+        )
+        (@subcommand index =>
+            (about: "Reads a fasta file and builds its index as finding overlaps would. Reports the reads, the size of the index and the time it takes to build")
+            (@arg IN_PATH: +required "Path to the input fasta file")
+            (@arg mode: -m --mode +takes_value "The mode to index for. Modes with search schemes index the reversed text too. (Default : kucherov_2)")
+            (@arg reversals: -r --reversals "Enables reversals of input strings")
+            (@arg no_n: -n --no_n "Omits N symbol from alphabet. Will remove N symbols from input file (with a warning)")
+        )
+        (@subcommand benchmark =>
+            (about: "Times each stage of finding overlaps in the given fasta files (or simulated reads) with each of the given modes. Prints a TSV line per run")
+            (@arg ERR_RATE: +required "The max rate of errors in an overlap")
+            (@arg THRESH: +required "Shortest allowed length of an overlap")
+            (@arg datasets: -d --datasets +takes_value +multiple "Fasta files to find overlaps in. (Default : simulated reads)")
+            (@arg modes: -m --modes +takes_value +multiple "Modes to compare. (Default : kucherov_2)")
+            (@arg repeats: --repeats +takes_value "Runs per dataset and mode. (Default : 1)")
+            (@arg output: -o --output +takes_value "Path of the output file written by each run. (Default : in the temporary directory)")
+            (@arg worker_threads: -w --worker_threads +takes_value "Number of worker threads used. Defaults to number of logical cpu cores")
+            (@arg reversals: -r --reversals "Enables reversals of input strings")
+            (@arg inclusions: -i --inclusions "Enables finding of inclusion overlaps")
+            (@arg edit_distance: -e --edit_distance "Uses Levenshtein / edit distance instead of Hamming distance")
+            (@arg no_n: -n --no_n "Omits N symbol from alphabet")
+            (@arg genome: -g --genome +takes_value "Fasta file of the genome to simulate reads from. (Default : a random genome)")
+            (@arg genome_len: --genome_len +takes_value "Length of the random genome. (Default : 10000)")
+            (@arg reads: --reads +takes_value "Number of simulated reads. (Default : 1000)")
+            (@arg lengths: -l --lengths +takes_value "Simulated read lengths 'LEN', 'MIN-MAX' or 'MEAN~SD'. (Default : 100-150)")
+            (@arg sub_rate: --sub_rate +takes_value "Probability of substituting a genome symbol. (Default : 0.01)")
+            (@arg ins_rate: --ins_rate +takes_value "Probability of inserting a random symbol after a genome symbol. (Default : 0)")
+            (@arg del_rate: --del_rate +takes_value "Probability of deleting a genome symbol. (Default : 0)")
+            (@arg n_rate: --n_rate +takes_value "Probability of replacing a genome symbol with N. (Default : 0)")
+            (@arg rev_rate: --rev_rate +takes_value "Probability of reverse-complementing a read. (Default : 0.5)")
+            (@arg seed: --seed +takes_value "Seed of the random number generator. (Default : 0)")
+        )
+    );
+    let matches = overlap_args(app)
+        .subcommand(overlap_args(SubCommand::with_name("overlap")
+            .about("Finds approximate suffix prefix overlaps from a given fasta file. The same as giving its arguments without a subcommand")))
+        .subcommand(graph_args(clap_app!(@app (SubCommand::with_name("graph"))
+            (about: "Builds a string graph from overlaps written by a run, as finding overlaps with --gfa (and the options that go with it) does")
+            (@arg OVERLAPS: +required "Path of the overlaps to build the graph from")
+            (@arg reads: -r --reads +takes_value +required "The fasta file of reads the overlaps are of")
+            (@arg no_n: -n --no_n "Removes N symbols from the reads, as finding overlaps with -n does. Needed for the overlaps of such a run")
+        )))
+        .get_matches();
 
-        (@arg format_line: -f --format_line "The first line of the output file will contain a TSV header line.")
-        (@arg reversals: -r --reversals "Enables reversals of input strings")
-        (@arg inclusions: -i --inclusions "Enables finding of inclusion overlaps (one string within another)")
-        (@arg edit_distance: -e --edit_distance "Uses Levenshtein / edit distance instead of Hamming distance")
-        (@arg verbose: -v --verbose +multiple "Prints completed steps of the run process")
-        (@arg greedy_output: -g --greedy_output "Threads print solutions to output greedily instead of storing them. Limited duplication may arise")
-        (@arg print: -p --print "For each solution printed to file, also prints a rough visualization to stdout (mostly for debugging purposes)")
-        (@arg no_n: -n --no_n "Omits N symbol from alphabet saving time. Will remove N symbols from input file (with a warning)")
-        (@arg track_progress: -t --track_progress "Prints progress bar for completed tasks and ETA to stdout")
-    ).get_matches();
+    if let Some(verify) = matches.subcommand_matches("verify") {
+        let ranges = CheckRanges {
+            patt_lens : parse_range(verify.value_of("lengths").unwrap_or("10-80")),
+            err_rates : verify.value_of("err_rates").unwrap_or("0.01,0.02,0.05,0.1").split(',')
+                .map(|x| x.trim().parse().expect("Couldn't interpret the error rates as numbers!"))
+                .collect(),
+            threshes : parse_range(verify.value_of("threshes").unwrap_or("5-25")),
+        };
+        return Task::VerifyMode(parse_mode_arg(verify.value_of("mode")), ranges);
+    }
+    if let Some(simulate) = matches.subcommand_matches("simulate") {
+        return Task::Simulate(parse_simulation(simulate));
+    }
+    if let Some(stats) = matches.subcommand_matches("stats") {
+        return Task::Stats(stats.value_of("OVERLAPS").unwrap().to_owned(), stats.value_of("reads").map(|x| x.to_owned()));
+    }
+    if let Some(conv) = matches.subcommand_matches("convert") {
+        let (input, output) = (conv.value_of("IN_PATH").unwrap(), conv.value_of("OUT_PATH").unwrap());
+        let format = |arg, path| match Format::parse(conv.value_of(arg), path) {
+            Ok(format) => format,
+            Err(problem) => Error::with_description(&problem, ErrorKind::InvalidValue).exit(),
+        };
+        return Task::Convert(ConvertConfig{
+            input :     input.to_owned(),
+            output :    output.to_owned(),
+            reads :     conv.value_of("reads").unwrap().to_owned(),
+            from :      format("from", input),
+            to :        format("to", output),
+            n_alphabet : !conv.is_present("no_n"),
+        });
+    }
+    if let Some(filt) = matches.subcommand_matches("filter") {
+        return Task::Filter(FilterConfig{
+            input :         filt.value_of("IN_PATH").unwrap().to_owned(),
+            output :        filt.value_of("OUT_PATH").unwrap().to_owned(),
+            expression :    filt.value_of("expression").map(|x| match Expr::parse(x) {
+                Ok(expr) => expr,
+                Err(problem) => Error::with_description(&problem, ErrorKind::InvalidValue).exit(),
+            }),
+            max_per_read :  filt.value_of("max_per_read")
+                .map(|x| x.parse().expect("Couldn't interpret the overlaps per read as a number!")),
+            keep_reads :    filt.value_of("keep_reads").map(|x| x.to_owned()),
+            drop_reads :    filt.value_of("drop_reads").map(|x| x.to_owned()),
+        });
+    }
+    if let Some(index) = matches.subcommand_matches("index") {
+        return Task::Index(parse_mode_arg(index.value_of("mode")), IndexConfig{
+            input :         index.value_of("IN_PATH").unwrap().to_owned(),
+            reversals :     index.is_present("reversals"),
+            n_alphabet :    !index.is_present("no_n"),
+        });
+    }
+    if let Some(graph) = matches.subcommand_matches("graph") {
+        let graph_config = parse_graph(&RunArgs{matches : graph, file : RunFile::default()}).unwrap_or_else(||
+            Error::with_description("Nothing to write. Give at least one of --gfa, --unitigs, --layout or --consensus.", ErrorKind::MissingRequiredArgument).exit());
+        return Task::Graph(graph.value_of("OVERLAPS").unwrap().to_owned(), graph.value_of("reads").unwrap().to_owned(),
+                           !graph.is_present("no_n"), graph_config);
+    }
+    if let Some(bench) = matches.subcommand_matches("benchmark") {
+        let modes : Vec<String> = match bench.values_of("modes") {
+            Some(values) => values.map(|x| x.to_owned()).collect(),
+            None => vec![modes::DEFAULT_MODE.to_owned()],
+        };
+        for m in modes.iter() {
+            parse_mode_arg(Some(m));
+        }
+        let output = match bench.value_of("output") {
+            Some(path) => path.to_owned(),
+            None => ::std::env::temp_dir().join("rust_overlaps_benchmark_output.tsv").to_string_lossy().into_owned(),
+        };
+        let config = Config{
+            input :             String::new(),
+            output :            output,
+            err_rate :          bench.value_of("ERR_RATE").unwrap().parse().expect("Couldn't interpret the error rate as a number!"),
+            thresh :            bench.value_of("THRESH").unwrap().parse().expect("Couldn't interpret the threshold as a number!"),
+            max_errors :        None,
+            worker_threads :    match bench.value_of("worker_threads") {
+                Some(s) => s.parse().unwrap(),
+                None => max(1, num_cpus::get()-1),
+            },
+            verbosity:          0,
+            reversals :         bench.is_present("reversals"),
+            inclusions :        bench.is_present("inclusions"),
+            edit_distance :     bench.is_present("edit_distance"),
+            scoring :           None,
+            stats_json :        None,
+            graph :             None,
+            greedy_output:      false,
+            sort_memory :       DEFAULT_SORT_MEMORY,
+            print:              false,
+            track_progress:     false,
+            format_line:        false,
+            provenance:         false,
+            n_alphabet :        !bench.is_present("no_n"),
+        };
+        assert!(config.thresh > 0, "ERROR! Threshold value must be strictly larger than 0.");
+        assert!(config.err_rate >= 0.0 && config.err_rate < 1.0, "ERROR! Error rate limit must be non-negative and smaller than 1. 0 <= e < 1.");
+        for m in modes.iter() {
+            check_settings(&parse_mode_arg(Some(m)), config.err_rate, config.thresh);
+        }
+        let mut simulation = parse_simulation(bench);
+        simulation.thresh = config.thresh as usize;
+        return Task::Benchmark(BenchConfig{
+            datasets :      bench.values_of("datasets").map(|v| v.map(|x| x.to_owned()).collect()).unwrap_or(Vec::new()),
+            simulation :    simulation,
+            modes :         modes,
+            repeats :       bench.value_of("repeats").unwrap_or("1").parse().expect("Couldn't interpret the repeats as a number!"),
+            config :        config,
+        });
+    }
 
-    let worker_threads = match matches.value_of("worker_threads") {
+    let matches = matches.subcommand_matches("overlap").unwrap_or(&matches);
+    let args = RunArgs {
+        matches : matches,
+        file : match matches.value_of("config") {
+            Some(path) => RunFile::read(path).unwrap_or_else(|problem|
+                Error::with_description(&problem, ErrorKind::InvalidValue).exit()),
+            None => RunFile::default(),
+        },
+    };
+    let required = |arg, key| args.value_of(arg).unwrap_or_else(||
+        Error::with_description(&format!("<{}> must be given, on the command line or as '{}' in the config file.", arg, key),
+                                ErrorKind::MissingRequiredArgument).exit());
+    let worker_threads = match args.value_of("worker_threads") {
         Some(s) => s.parse().unwrap(),
         None => max(1, num_cpus::get()-1),
     };
-    let mode : Mode = match matches.value_of("mode") {
-        Some(s) => modes::get_mode(s),
-        _ => modes::default_mode(),
+    if args.is_present("list_modes") {
+        print!("{}", modes::describe_modes());
+        process::exit(0);
+    }
+    let mode = parse_mode_arg(args.value_of("mode"));
+    let err_rate : f32 = required("ERR_RATE", "err_rate").parse().expect("Couldn't interpret the error rate as a number!");
+    let scoring = match args.value_of("scoring") {
+        Some(s) => {
+            let costs : Vec<u32> = s.split(',')
+                .map(|x| x.trim().parse().expect("Couldn't interpret the scoring costs as numbers!"))
+                .collect();
+            assert!(costs.len() == 3, "ERROR! Scoring expects three costs 'SUB,GAP_OPEN,GAP_EXTEND'.");
+            let max_score_rate = match args.value_of("score_rate") {
+                Some(r) => r.parse().expect("Couldn't interpret the score rate as a number!"),
+                None => err_rate,
+            };
+            Some(Scoring{
+                substitution : costs[0],
+                gap_open : costs[1],
+                gap_extend : costs[2],
+                max_score_rate : max_score_rate,
+            })
+        },
+        None => None,
     };
 
     let config = Config{
         //required
-        input  :            matches.value_of("IN_PATH").unwrap().to_owned(),
-        output :            matches.value_of("OUT_PATH").unwrap().to_owned(),
-        err_rate :          matches.value_of("ERR_RATE").unwrap().parse().unwrap(),
-        thresh :            matches.value_of("THRESH").unwrap().parse().unwrap(),
+        input  :            required("IN_PATH", "input").to_owned(),
+        output :            required("OUT_PATH", "output").to_owned(),
+        err_rate :          err_rate,
+        thresh :            required("THRESH", "thresh").parse().expect("Couldn't interpret the threshold as a number!"),
+        max_errors :        mode.get_max_errors(),
 
         //options
         worker_threads :    worker_threads,
-        verbosity:          min(matches.occurrences_of("verbose") as u8, 2),
+        verbosity:          min(args.occurrences_of("verbose"), 2) as u8,
 
         //opt-in
-        reversals :         if matches.occurrences_of("reversals")        >= 1 {true} else {false},
-        inclusions :        if matches.occurrences_of("inclusions")       >= 1 {true} else {false},
-        edit_distance :     if matches.occurrences_of("edit_distance")    >= 1 {true} else {false},
-        greedy_output:      if matches.occurrences_of("greedy_output")    >= 1 {true} else {false},
-        print:              if matches.occurrences_of("print")            >= 1 {true} else {false},
-        track_progress:     if matches.occurrences_of("track_progress")   >= 1 {true} else {false},
-        format_line:        if matches.occurrences_of("format_line")      >= 1 {true} else {false},
+        reversals :         args.is_present("reversals"),
+        inclusions :        args.is_present("inclusions"),
+        edit_distance :     args.is_present("edit_distance"),
+        scoring :           scoring,
+        stats_json :        args.value_of("stats_json").map(|x| x.to_owned()),
+        graph :             parse_graph(&args),
+        greedy_output:      args.is_present("greedy_output"),
+        sort_memory :       match args.value_of("sort_memory") {
+            Some(mb) => mb.parse::<usize>().expect("Couldn't interpret the sort memory as a number of megabytes!") * 1024 * 1024,
+            None => DEFAULT_SORT_MEMORY,
+        },
+        print:              args.is_present("print"),
+        track_progress:     args.is_present("track_progress"),
+        format_line:        args.is_present("format_line"),
+        provenance:         args.is_present("provenance"),
 
         //opt-out
-        n_alphabet :        if matches.occurrences_of("no_n")             == 0 {true} else {false},
+        n_alphabet :        !args.is_present("no_n"),
     };
 
+    if config.graph.is_some() && !config.inclusions {
+        Error::with_description("The string graph (--gfa, --unitigs, --layout or --consensus) needs the inclusions. Run with flag -i.",
+                                ErrorKind::MissingRequiredArgument).exit();
+    }
     assert!(config.thresh > 0, "ERROR! Threshold value must be strictly larger than 0.");
     assert!(config.err_rate >= 0.0 && config.err_rate < 1.0, "ERROR! Error rate limit must be non-negative and smaller than 1. 0 <= e < 1.");
+    assert!(config.scoring.is_none() || config.edit_distance, "ERROR! Weighted scoring requires edit distance. Run with flag -e.");
+    check_settings(&mode, config.err_rate, config.thresh);
     if !config.reversals{
         println!("WARNING! Reversals are NOT enabled by default. Run with -r flag to enable reversals.");
     }
-    (mode, config)
+    Task::Overlaps(mode, config)
+}
+
+// the arguments of finding overlaps, shared by the overlap subcommand and the positional form without one
+fn overlap_args<'a, 'b>(app : App<'a, 'b>) -> App<'a, 'b> {
+    let app = clap_app!(@app (app)
+        (@arg IN_PATH: +takes_value required_unless[list_modes config] "Path to the input fasta file")
+        (@arg OUT_PATH: +takes_value required_unless[list_modes config] "Path of desired output file")
+        (@arg ERR_RATE: +takes_value required_unless[list_modes config] "The max rate of errors in an overlap (ignored by modes with a fixed error limit)")
+        (@arg THRESH: +takes_value required_unless[list_modes config] "Shortest allowed length of an overlap")
+
+        (@arg worker_threads: -w --worker_threads +takes_value "Number of worker threads used. Defaults to number of logical cpu cores")
+        (@arg mode: -m --mode +takes_value "Uses the given filtering scheme mode. Parameters follow in order after underscores or by name, i.e. 'kucherov_3' or 'kucherov:s=3'. See --list_modes. (Default : kucherov_2)")
+        (@arg list_modes: --list_modes "Lists the available modes with their parameters and exits")
+        (@arg config: -c --config +takes_value "Reads the settings of the run from this TOML file, named as the long options. Arguments given on the command line override it")
+
+        (@arg format_line: -f --format_line "The first line of the output file will contain a TSV header line.")
+        (@arg provenance: --provenance "Writes how the run was produced (version, configuration, mode, input checksum and time) as comment lines at the top of the output and of the GFA of --gfa")
+        (@arg reversals: -r --reversals "Enables reversals of input strings")
+        (@arg inclusions: -i --inclusions "Enables finding of inclusion overlaps (one string within another)")
+        (@arg edit_distance: -e --edit_distance "Uses Levenshtein / edit distance instead of Hamming distance")
+        (@arg scoring: -s --scoring +takes_value "Verifies overlaps with weighted costs 'SUB,GAP_OPEN,GAP_EXTEND' as well as unit edit costs: an overlap must be within both the error rate and --score_rate. Requires -e. Score and identity are appended to the output")
+        (@arg score_rate: --score_rate +takes_value "The max weighted cost per overlapping symbol when using --scoring. (Default : ERR_RATE)")
+        (@arg stats_json: --stats_json +takes_value "Writes statistics of the search and verification to this path as JSON at the end of the run")
+        (@arg verbose: -v --verbose +multiple "Prints completed steps of the run process")
+        (@arg greedy_output: -g --greedy_output "Threads print solutions to output greedily instead of storing them. The output is unsorted")
+        (@arg sort_memory: --sort_memory +takes_value "Megabytes of solutions kept in memory while sorting the output. Beyond this, sorted runs are spilled to disk beside the output file and merged. Ignored with -g. (Default : 1024)")
+        (@arg print: -p --print "For each solution printed to file, also prints a rough visualization to stdout (mostly for debugging purposes)")
+        (@arg no_n: -n --no_n "Omits N symbol from alphabet saving time. Will remove N symbols from input file (with a warning)")
+        (@arg track_progress: -t --track_progress "Prints progress bar for completed tasks and ETA to stdout")
+    );
+    graph_args(app)
+}
+
+// the arguments of building a string graph, shared by the overlap and graph subcommands
+fn graph_args<'a, 'b>(app : App<'a, 'b>) -> App<'a, 'b> {
+    clap_app!(@app (app)
+        (@arg gfa: --gfa +takes_value "Builds a string graph from the overlaps, removing contained reads and transitive overlaps, and writes it to this path as GFA. Needs the inclusions (-i) so that contained reads are known")
+        (@arg unitigs: --unitigs +takes_value "Builds the string graph (see --gfa), and writes the sequences of its unitigs (non-branching paths) to this path as fasta")
+        (@arg layout: --layout +takes_value "Builds the string graph (see --gfa), and writes the order and offsets of the reads in each unitig to this path as TSV")
+        (@arg consensus: --consensus +takes_value "Builds the string graph (see --gfa), and writes the sequences of its unitigs to this path as fasta, polished by a majority vote of their reads")
+        (@arg fuzz: --fuzz +takes_value "Symbols of slack allowed when removing transitive overlaps from the string graph. (Default : 10)")
+        (@arg weak_overlaps: --weak_overlaps +takes_value "Cleans the string graph of overlaps shorter than this fraction of the longest overlap of the same read end, i.e. 0.7. (Default : off)")
+        (@arg trim_tips: --trim_tips +takes_value "Cleans the string graph of dead-end paths of at most this many reads. (Default : off)")
+        (@arg pop_bubbles: --pop_bubbles +takes_value "Cleans the string graph of bubbles with paths of at most this many reads, keeping the path with the fewest errors. (Default : off)")
+    )
+}
+
+// None unless one of the outputs of the string graph is given
+fn parse_graph(args : &RunArgs) -> Option<GraphConfig> {
+    if !["gfa", "unitigs", "layout", "consensus"].iter().any(|&arg| args.value_of(arg).is_some()) {
+        return None;
+    }
+    Some(GraphConfig{
+        gfa_path :      args.value_of("gfa").map(|x| x.to_owned()),
+        unitigs_path :  args.value_of("unitigs").map(|x| x.to_owned()),
+        layout_path :   args.value_of("layout").map(|x| x.to_owned()),
+        consensus_path : args.value_of("consensus").map(|x| x.to_owned()),
+        fuzz :          args.value_of("fuzz").map(|x| x.parse().expect("Couldn't interpret the fuzz as a number!")).unwrap_or(DEFAULT_FUZZ),
+        weak_ratio :    args.value_of("weak_overlaps").map(|x| x.parse().expect("Couldn't interpret the weak overlap ratio as a number!")).unwrap_or(0.0),
+        max_tip :       args.value_of("trim_tips").map(|x| x.parse().expect("Couldn't interpret the tip length as a number!")).unwrap_or(0),
+        max_bubble :    args.value_of("pop_bubbles").map(|x| x.parse().expect("Couldn't interpret the bubble length as a number!")).unwrap_or(0),
+    })
+}
+
+// the arguments of finding overlaps: those on the command line, else those in the config file
+struct RunArgs<'a> {
+    matches : &'a ArgMatches<'a>,
+    file : RunFile,
+}
+
+impl<'a> RunArgs<'a> {
+    fn value_of(&self, arg : &str) -> Option<&str> {
+        self.matches.value_of(arg).or_else(|| self.file.value_of(arg))
+    }
+
+    fn is_present(&self, arg : &str) -> bool {
+        self.matches.is_present(arg) || self.file.is_set(arg)
+    }
+
+    fn occurrences_of(&self, arg : &str) -> u64 {
+        match self.matches.occurrences_of(arg) {
+            0 => self.file.value_of(arg).map(|x| x.parse().unwrap_or_else(|_|
+                Error::with_description(&format!("In the config file: Setting '{}' must be a non-negative integer.", arg),
+                                        ErrorKind::InvalidValue).exit())).unwrap_or(0),
+            n => n,
+        }
+    }
+}
+
+// the options of the simulate subcommand, which the benchmark subcommand shares
+fn parse_simulation(args : &ArgMatches) -> SimConfig {
+    let rate = |name, default| -> f64 {
+        let r : f64 = args.value_of(name).unwrap_or(default).parse()
+            .expect(&format!("Couldn't interpret the {} as a number!", name));
+        assert!(r >= 0.0 && r <= 1.0, "ERROR! The {} must be between 0 and 1.", name);
+        r
+    };
+    let sim = SimConfig {
+        reads_path :    args.value_of("OUT_PATH").unwrap_or("").to_owned(),
+        truth_path :    args.value_of("TRUTH_PATH").unwrap_or("").to_owned(),
+        genome_path :   args.value_of("genome").map(|x| x.to_owned()),
+        genome_len :    args.value_of("genome_len").unwrap_or("10000").parse()
+            .expect("Couldn't interpret the genome length as a number!"),
+        num_reads :     args.value_of("reads").unwrap_or("1000").parse()
+            .expect("Couldn't interpret the number of reads as a number!"),
+        lengths :       match LengthDist::parse(args.value_of("lengths").unwrap_or("100-150")) {
+            Ok(dist) => dist,
+            Err(problem) => Error::with_description(&problem, ErrorKind::InvalidValue).exit(),
+        },
+        sub_rate :      rate("sub_rate", "0.01"),
+        ins_rate :      rate("ins_rate", "0"),
+        del_rate :      rate("del_rate", "0"),
+        n_rate :        rate("n_rate", "0"),
+        rev_rate :      rate("rev_rate", "0.5"),
+        thresh :        args.value_of("thresh").unwrap_or("1").parse()
+            .expect("Couldn't interpret the threshold as a number!"),
+        seed :          args.value_of("seed").unwrap_or("0").parse()
+            .expect("Couldn't interpret the seed as a number!"),
+    };
+    assert!(sim.sub_rate + sim.del_rate + sim.n_rate <= 1.0,
+            "ERROR! The substitution, deletion and N rates may not sum to more than 1.");
+    sim
+}
+
+fn parse_mode_arg(arg : Option<&str>) -> Mode {
+    match arg {
+        Some(s) => match modes::parse_mode(s) {
+            Ok(mode) => mode,
+            Err(problem) => Error::with_description(&problem, ErrorKind::InvalidValue).exit(),
+        },
+        _ => modes::default_mode(),
+    }
+}
+
+fn check_settings(mode : &Mode, err_rate : f32, thresh : i32) {
+    if let Err(problem) = mode.check_settings(err_rate, thresh) {
+        Error::with_description(&problem, ErrorKind::InvalidValue).exit();
+    }
+}
+
+// inclusive range 'MIN-MAX', or a single value
+fn parse_range(arg : &str) -> (i32, i32) {
+    let bounds : Vec<i32> = arg.split('-')
+        .map(|x| x.trim().parse().expect("Couldn't interpret the range bounds as numbers!"))
+        .collect();
+    match bounds.len() {
+        1 => (bounds[0], bounds[0]),
+        2 => (bounds[0], bounds[1]),
+        _ => panic!("ERROR! Expected a range of the form 'MIN-MAX'."),
+    }
 }
@@ -0,0 +1,238 @@
+use bio::io::fasta;
+use rand::{Rng, SeedableRng, StdRng};
+use rand::distributions::{Normal, IndependentSample};
+
+use std::io;
+use std::io::{Write, BufWriter};
+use std::fs::File;
+use std::cmp::{min, max};
+
+////////////////////////////////////////////////////////////
+
+use prepare::reverse_complement;
+use useful::Orientation;
+
+/*
+Generates reads for testing and benchmarking. Reads are sampled from a random genome (or the records
+of a given fasta file), and given random substitutions, insertions, deletions and N symbols.
+Some are reverse-complemented. The reads are written as fasta, and the overlaps they truly have
+(because they were sampled from overlapping regions of the genome) are written in the solver's output format.
+*/
+#[derive(Debug)]
+pub struct SimConfig {
+    pub reads_path : String,
+    pub truth_path : String,
+    pub genome_path : Option<String>,
+    pub genome_len : usize, // when generating a random genome
+    pub num_reads : usize,
+    pub lengths : LengthDist,
+    pub sub_rate : f64,
+    pub ins_rate : f64,
+    pub del_rate : f64,
+    pub n_rate : f64,
+    pub rev_rate : f64,
+    pub thresh : usize, // shortest true overlap written
+    pub seed : usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum LengthDist {
+    Fixed(usize),
+    Uniform(usize, usize), // inclusive
+    Normal(f64, f64),      // mean, standard deviation
+}
+
+impl LengthDist {
+    // 'LEN', 'MIN-MAX' or 'MEAN~SD'
+    pub fn parse(arg : &str) -> Result<LengthDist, String> {
+        let bad = format!("Couldn't interpret read lengths '{}'. Expected 'LEN', 'MIN-MAX' or 'MEAN~SD'.", arg);
+        let dist = if let Some(i) = arg.find('~') {
+            LengthDist::Normal(arg[..i].trim().parse().map_err(|_| bad.clone())?, arg[i+1..].trim().parse().map_err(|_| bad.clone())?)
+        } else if let Some(i) = arg.find('-') {
+            LengthDist::Uniform(arg[..i].trim().parse().map_err(|_| bad.clone())?, arg[i+1..].trim().parse().map_err(|_| bad.clone())?)
+        } else {
+            LengthDist::Fixed(arg.trim().parse().map_err(|_| bad.clone())?)
+        };
+        match dist {
+            LengthDist::Fixed(0) => Err("Reads must be at least 1 symbol long.".to_owned()),
+            LengthDist::Uniform(lo, hi) if lo == 0 || lo > hi => Err(format!("Bad read length range '{}'.", arg)),
+            LengthDist::Normal(mean, sd) if mean < 1.0 || sd < 0.0 => Err(format!("Bad read length distribution '{}'.", arg)),
+            dist => Ok(dist),
+        }
+    }
+
+    fn sample(&self, rng : &mut StdRng) -> usize {
+        match *self {
+            LengthDist::Fixed(len) => len,
+            LengthDist::Uniform(lo, hi) => rng.gen_range(lo, hi + 1),
+            LengthDist::Normal(mean, sd) => {
+                let len = Normal::new(mean, sd).ind_sample(rng).round();
+                max(1, len as i64) as usize
+            },
+        }
+    }
+}
+
+struct SimRead {
+    name : String,
+    seq : Vec<u8>,          // as it appears on the genome (not yet reverse-complemented)
+    chrom : usize,
+    start : usize,          // in the genome, inclusive
+    end : usize,            // in the genome, exclusive
+    starts : Vec<usize>,    // index in seq of each genome symbol start..end, and then seq.len()
+    error_positions : Vec<usize>,
+    reversed : bool,
+}
+
+static ALPHABET : &'static [u8] = b"ACGT";
+
+// returns the number of reads and true overlaps written
+pub fn simulate(sim : &SimConfig) -> Result<(usize, usize), io::Error> {
+    let mut rng : StdRng = SeedableRng::from_seed(&[sim.seed][..]);
+    let genome = match sim.genome_path {
+        Some(ref path) => read_genome(path)?,
+        None => vec![(0..sim.genome_len).map(|_| ALPHABET[rng.gen_range(0, 4)]).collect()],
+    };
+    let total_len : usize = genome.iter().map(|c| c.len()).sum();
+    assert!(total_len > 0, "ERROR! The genome to simulate reads from is empty.");
+
+    let digits = format!("{}", max(1, sim.num_reads) - 1).len();
+    let mut reads = Vec::with_capacity(sim.num_reads);
+    for i in 0..sim.num_reads {
+        let mut read = sample_read(&genome, total_len, sim, &mut rng);
+        read.name = format!("r{:0width$}", i, width = digits);
+        reads.push(read);
+    }
+
+    let f = File::create(&sim.reads_path)?;
+    let mut wrt_buf = BufWriter::new(f);
+    for read in reads.iter() {
+        let seq = if read.reversed {reverse_complement(&read.seq)} else {read.seq.clone()};
+        write!(wrt_buf, ">{} {}:{}-{} {}\n", read.name, read.chrom, read.start, read.end,
+               if read.reversed {'-'} else {'+'})?;
+        wrt_buf.write_all(&seq)?;
+        wrt_buf.write_all(b"\n")?;
+    }
+    wrt_buf.flush()?;
+
+    let truth = true_overlaps(&reads, sim.thresh);
+    let f = File::create(&sim.truth_path)?;
+    let mut wrt_buf = BufWriter::new(f);
+    for line in truth.iter() {
+        write!(wrt_buf, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+               line.0, line.1, line.2, line.3, line.4, line.5, line.6, line.7)?;
+    }
+    wrt_buf.flush()?;
+    Ok((reads.len(), truth.len()))
+}
+
+// every record of the fasta file is a separate sequence of the genome
+fn read_genome(path : &str) -> Result<Vec<Vec<u8>>, io::Error> {
+    let f = File::open(path)
+        .expect(&format!("Failed to open genome file at {:?}\n", path));
+    let mut genome = Vec::new();
+    for record in fasta::Reader::new(f).records() {
+        let record = record?;
+        // symbols other than ACGT become N
+        genome.push(record.seq().iter().map(|c| match c.to_ascii_uppercase() {
+            x @ b'A' | x @ b'C' | x @ b'G' | x @ b'T' => x,
+            _ => b'N',
+        }).collect());
+    }
+    Ok(genome)
+}
+
+fn sample_read(genome : &[Vec<u8>], total_len : usize, sim : &SimConfig, rng : &mut StdRng) -> SimRead {
+    // the sequence is chosen with probability proportional to its length
+    let mut at = rng.gen_range(0, total_len);
+    let mut chrom = 0;
+    while at >= genome[chrom].len() {
+        at -= genome[chrom].len();
+        chrom += 1;
+    }
+    let source = &genome[chrom];
+    let len = min(sim.lengths.sample(rng), source.len());
+    let start = rng.gen_range(0, source.len() - len + 1);
+
+    let mut seq = Vec::with_capacity(len + len / 10);
+    let mut starts = Vec::with_capacity(len + 1);
+    let mut error_positions = Vec::new();
+    for &c in source[start..start+len].iter() {
+        starts.push(seq.len());
+        let roll : f64 = rng.gen();
+        if roll < sim.del_rate {
+            error_positions.push(seq.len());
+            continue;
+        }
+        let roll = roll - sim.del_rate;
+        if roll < sim.sub_rate {
+            error_positions.push(seq.len());
+            let others : Vec<u8> = ALPHABET.iter().cloned().filter(|&x| x != c).collect();
+            seq.push(others[rng.gen_range(0, others.len())]);
+        } else if roll - sim.sub_rate < sim.n_rate {
+            error_positions.push(seq.len());
+            seq.push(b'N');
+        } else {
+            seq.push(c);
+        }
+        if rng.gen::<f64>() < sim.ins_rate {
+            error_positions.push(seq.len());
+            seq.push(ALPHABET[rng.gen_range(0, 4)]);
+        }
+    }
+    starts.push(seq.len());
+    if seq.is_empty() {
+        // everything was deleted. keep one symbol so that the read is valid
+        seq.push(source[start]);
+        for s in starts[1..].iter_mut() {
+            *s = 1;
+        }
+    }
+    SimRead {
+        name : String::new(),
+        seq : seq,
+        chrom : chrom,
+        start : start,
+        end : start + len,
+        starts : starts,
+        error_positions : error_positions,
+        reversed : rng.gen::<f64>() < sim.rev_rate,
+    }
+}
+
+/*
+Pairs of reads sampled from overlapping regions of the same sequence. The geometry of each overlap
+follows from where the overlapping region lies in both reads. For K, the simulated errors of both reads within
+the overlapping region are counted. This is an upper bound on the distance between the overlapping sections.
+Overlaps are sorted as in the solver's output. Inclusions are included.
+*/
+fn true_overlaps(reads : &[SimRead], thresh : usize) -> Vec<(String, String, Orientation, i32, i32, usize, usize, usize)> {
+    let mut by_position : Vec<&SimRead> = reads.iter().collect();
+    by_position.sort_by_key(|r| (r.chrom, r.start));
+    let mut overlaps = Vec::new();
+    for (i, first) in by_position.iter().enumerate() {
+        for second in by_position[i+1..].iter() {
+            if second.chrom != first.chrom || second.start >= first.end {
+                break;
+            }
+            let (a, b) = if first.name < second.name {(*first, *second)} else {(*second, *first)};
+            let (from, to) = (max(a.start, b.start), min(a.end, b.end));
+            let (a1, a_end) = (a.starts[from - a.start], a.starts[to - a.start]);
+            let (b1, b_end) = (b.starts[from - b.start], b.starts[to - b.start]);
+            let (ola, olb) = (a_end - a1, b_end - b1);
+            if ola == 0 || olb == 0 || max(ola, olb) < thresh {
+                continue;
+            }
+            let (a3, b3) = (a.seq.len() - a_end, b.seq.len() - b_end);
+            let errors = a.error_positions.iter().filter(|&&p| p >= a1 && p < a_end).count()
+                + b.error_positions.iter().filter(|&&p| p >= b1 && p < b_end).count();
+            let (oha, ohb) = (a1 as i32 - b1 as i32, b3 as i32 - a3 as i32);
+            // A is never reversed. if it is, the overlap is seen from the other strand
+            let (oha, ohb) = if a.reversed {(-ohb, -oha)} else {(oha, ohb)};
+            let orientation = if a.reversed == b.reversed {Orientation::Normal} else {Orientation::Reversed};
+            overlaps.push((a.name.clone(), b.name.clone(), orientation, oha, ohb, ola, olb, errors));
+        }
+    }
+    overlaps.sort();
+    overlaps
+}
@@ -69,6 +69,8 @@ pub mod solutions{
         pub overlap_a : usize,
         pub overlap_b : usize,
         pub errors : u32,
+        pub score : Option<u32>,    //only when verifying with weighted scoring
+        pub identity : Option<f32>, //only when verifying with weighted scoring
     }
 
     impl Solution{
@@ -138,6 +140,7 @@ representations and is queried throughout the run, also never changing after bei
 pub mod run_config{
     extern crate bidir_map;
     use bidir_map::BidirMap;
+    use graph::GraphConfig;
 
     #[derive(Debug)]
     pub struct Maps{
@@ -199,6 +202,19 @@ pub mod run_config{
         }
     }
 
+    /*
+    Weighted costs for verifying overlaps (edit distance only).
+    A gap of length g costs gap_open + g*gap_extend.
+    Overlaps verify if their cost is at most max_score_rate per overlapping symbol.
+    */
+    #[derive(Debug, Clone)]
+    pub struct Scoring{
+        pub substitution : u32,
+        pub gap_open : u32,
+        pub gap_extend : u32,
+        pub max_score_rate : f32,
+    }
+
     pub static N_ALPH : &'static [u8] = b"ACGNT";
     pub static ALPH : &'static [u8] = b"ACGT";
 
@@ -211,13 +227,19 @@ pub mod run_config{
         pub output : String,
         pub err_rate : f32,
         pub thresh : i32,
+        pub max_errors : Option<i32>, //set by modes with a fixed error limit. overrides err_rate
 
         //optional
         pub format_line: bool,
+        pub provenance: bool,            //writes how the run was produced as comments at the top of the output
         pub greedy_output: bool,
+        pub sort_memory : usize,         //bytes of solutions held in memory before spilling to disk (without -g)
         pub reversals : bool,
         pub inclusions : bool,
         pub edit_distance : bool,
+        pub scoring : Option<Scoring>,
+        pub stats_json : Option<String>, //path to write the search statistics to
+        pub graph : Option<GraphConfig>, //builds a string graph from the solutions if set
         pub verbosity: u8,
         pub print: bool,
         pub n_alphabet: bool,
@@ -233,5 +255,206 @@ pub mod run_config{
                 &ALPH
             }
         }
+
+        // the most errors permitted in an overlap of the given length
+        pub fn max_errors_for(&self, overlap_len : usize) -> i32 {
+            match self.max_errors {
+                Some(k) => k,
+                None => (self.err_rate * overlap_len as f32).floor() as i32,
+            }
+        }
+
+        // bounds for the length of the B overlap when A's overlap has the given length (edit distance)
+        pub fn shortest_b_overlap(&self, a_overlap : usize) -> usize {
+            match self.max_errors {
+                Some(k) => a_overlap.saturating_sub(k as usize),
+                None => (a_overlap as f32 * (1.0-self.err_rate)).ceil() as usize,
+            }
+        }
+
+        pub fn longest_b_overlap(&self, a_overlap : usize) -> usize {
+            match self.max_errors {
+                Some(k) => a_overlap + k as usize,
+                None => (a_overlap as f32 / (1.0-self.err_rate)).floor() as usize,
+            }
+        }
+    }
+}
+
+
+/*
+Measurements of a run, for comparing modes and parameters.
+The counters are shared by the worker threads. Each task adds its totals once it is done.
+The stages are those of the search and verification, and solve() adds the others in the order they happen.
+Candidate generation and verification are timed within the tasks, so these times are summed over all worker threads.
+*/
+pub mod run_stats{
+    use std::fmt;
+    use std::cmp::max;
+    use std::time::Duration;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use super::run_config::Maps;
+    use super::useful::for_reversed_string;
+
+    // how many of the reads with the most candidates per solution are reported
+    pub static WORST_READS : usize = 10;
+
+    #[derive(Debug, Default)]
+    pub struct Counters{
+        pub search_nodes : AtomicUsize,
+        pub candidates_generated : AtomicUsize, // including those found by several filters
+        pub candidates_verified : AtomicUsize,
+        pub candidates_passed : AtomicUsize,    // before removing solutions found by several tasks
+        pub intervals_skipped : AtomicUsize,    // suffix array intervals already reported for the same overlap
+        pub generation_nanos : AtomicUsize,
+        pub verification_nanos : AtomicUsize,
+        pub filter_nodes : Mutex<Vec<usize>>,         // indexed by the first block of the filter
+        pub generated_per_errors : Mutex<Vec<usize>>, // indexed by the errors of the search
+        pub worst_reads : Mutex<Vec<ReadBlowup>>,     // worst first
+    }
+
+    // the candidates verified for the pattern of one task, and how many of them became solutions
+    #[derive(Debug, Clone)]
+    pub struct ReadBlowup{
+        pub id : usize,
+        pub candidates : usize,
+        pub solutions : usize,
+    }
+
+    impl ReadBlowup{
+        pub fn ratio(&self) -> f64 {
+            self.candidates as f64 / max(1, self.solutions) as f64
+        }
+    }
+
+    impl Counters{
+        // adds the tallies of one pattern's search
+        pub fn add_tallies(&self, filter_nodes : &[usize], generated_per_errors : &[usize]){
+            add_elementwise(&mut self.filter_nodes.lock().unwrap(), filter_nodes);
+            add_elementwise(&mut self.generated_per_errors.lock().unwrap(), generated_per_errors);
+        }
+
+        pub fn add_read(&self, read : ReadBlowup){
+            let mut worst = self.worst_reads.lock().unwrap();
+            if worst.len() == WORST_READS && worst[WORST_READS-1].ratio() >= read.ratio() {
+                return;
+            }
+            let at = worst.iter().position(|r| r.ratio() < read.ratio()).unwrap_or(worst.len());
+            worst.insert(at, read);
+            worst.truncate(WORST_READS);
+        }
+    }
+
+    pub fn add_elementwise(sums : &mut Vec<usize>, tallies : &[usize]){
+        if sums.len() < tallies.len() {
+            sums.resize(tallies.len(), 0);
+        }
+        for (sum, tally) in sums.iter_mut().zip(tallies.iter()) {
+            *sum += *tally;
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct RunStats{
+        pub stages : Vec<(&'static str, Duration)>, // in the order they happened
+        pub search_nodes : usize,
+        pub candidates_generated : usize,
+        pub candidates_verified : usize,
+        pub candidates_passed : usize,
+        pub intervals_skipped : usize,
+        pub solutions : usize,
+        pub filter_nodes : Vec<usize>,
+        pub generated_per_errors : Vec<usize>,
+        pub solutions_per_errors : Vec<usize>,
+        pub worst_reads : Vec<(String, bool, ReadBlowup)>, // with the name, and whether the pattern was reversed
+    }
+
+    impl RunStats{
+        pub fn new(counters : &Counters, solutions_per_errors : Vec<usize>, maps : &Maps) -> RunStats {
+            let nanos = |n : usize| Duration::new((n / 1_000_000_000) as u64, (n % 1_000_000_000) as u32);
+            RunStats {
+                stages : vec![
+                    ("candidate generation", nanos(counters.generation_nanos.load(Ordering::Relaxed))),
+                    ("verification", nanos(counters.verification_nanos.load(Ordering::Relaxed))),
+                ],
+                search_nodes : counters.search_nodes.load(Ordering::Relaxed),
+                candidates_generated : counters.candidates_generated.load(Ordering::Relaxed),
+                candidates_verified : counters.candidates_verified.load(Ordering::Relaxed),
+                candidates_passed : counters.candidates_passed.load(Ordering::Relaxed),
+                intervals_skipped : counters.intervals_skipped.load(Ordering::Relaxed),
+                solutions : solutions_per_errors.iter().sum(),
+                filter_nodes : counters.filter_nodes.lock().unwrap().clone(),
+                generated_per_errors : counters.generated_per_errors.lock().unwrap().clone(),
+                solutions_per_errors : solutions_per_errors,
+                worst_reads : counters.worst_reads.lock().unwrap().iter()
+                    .map(|r| (maps.get_name_for(r.id).to_owned(), for_reversed_string(r.id), r.clone()))
+                    .collect(),
+            }
+        }
+
+        pub fn seconds(&self, stage : &str) -> f64 {
+            self.stages.iter()
+                .filter(|&&(name, _)| name == stage)
+                .map(|&(_, d)| seconds(d))
+                .sum()
+        }
+
+        // the fraction of verified candidates that became solutions
+        pub fn pass_rate(&self) -> f64 {
+            if self.candidates_verified == 0 {
+                0.0
+            } else {
+                self.candidates_passed as f64 / self.candidates_verified as f64
+            }
+        }
+
+        pub fn to_json(&self) -> String {
+            let list = |v : &[usize]| v.iter().map(|x| format!("{}", x)).collect::<Vec<_>>().join(", ");
+            let stages : Vec<String> = self.stages.iter()
+                .map(|&(name, d)| format!("    {{\"stage\": \"{}\", \"seconds\": {:.6}}}", name, seconds(d)))
+                .collect();
+            let reads : Vec<String> = self.worst_reads.iter()
+                .map(|&(ref name, reversed, ref r)| format!(
+                    "    {{\"read\": \"{}\", \"reversed\": {}, \"candidates\": {}, \"solutions\": {}, \"blowup\": {:.4}}}",
+                    json_escape(name), reversed, r.candidates, r.solutions, r.ratio()))
+                .collect();
+            format!("{{\n  \"search_nodes\": {},\n  \"filter_nodes\": [{}],\n  \"candidates_generated\": {},\n  \"intervals_skipped\": {},\n  \
+                     \"generated_per_errors\": [{}],\n  \"candidates_verified\": {},\n  \"candidates_passed\": {},\n  \"solutions\": {},\n  \
+                     \"solutions_per_errors\": [{}],\n  \"pass_rate\": {:.6},\n  \"worst_reads\": [\n{}\n  ],\n  \
+                     \"stages\": [\n{}\n  ]\n}}\n",
+                    self.search_nodes, list(&self.filter_nodes), self.candidates_generated, self.intervals_skipped,
+                    list(&self.generated_per_errors), self.candidates_verified, self.candidates_passed, self.solutions,
+                    list(&self.solutions_per_errors), self.pass_rate(), reads.join(",\n"), stages.join(",\n"))
+        }
+    }
+
+    fn json_escape(s : &str) -> String {
+        let mut escaped = String::new();
+        for c in s.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    pub fn seconds(d : Duration) -> f64 {
+        d.as_secs() as f64 + d.subsec_nanos() as f64 * 1e-9
+    }
+
+    impl fmt::Display for RunStats {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "OK stage timings:\n")?;
+            for &(name, duration) in self.stages.iter() {
+                let note = if name == "candidate generation" || name == "verification" {" (summed over threads)"} else {""};
+                write!(f, "    {:<22}{:>10.3} sec{}\n", name, seconds(duration), note)?;
+            }
+            write!(f, "OK visited {} search nodes, generated {} candidates and verified {}, finding {} solutions.\n",
+                   self.search_nodes, self.candidates_generated, self.candidates_verified, self.solutions)
+        }
     }
 }
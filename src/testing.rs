@@ -6,16 +6,37 @@ the right solutions, using some predetermined inputs in th text_input directory.
 mod tests {
     use std;
     use setup;
+    use verification;
+    use lossless::{self, CheckRanges};
+    use oracle;
+    use simulate::{self, SimConfig, LengthDist};
+    use rand::{Rng, SeedableRng, StdRng};
     use search;
     use std::fs::File;
     use useful::Orientation::{Normal, Reversed};
     use modes;
     use solve;
+    use write_solution;
     use std::collections::HashSet;
     use prepare;
-    use structs::run_config::{Config, Maps};
+    use structs::run_config::{Config, Maps, Scoring};
+    use structs::run_stats;
+    use structs::solutions::Solution;
+    use consensus;
+    use tsv;
+    use external_sort::{self, SolutionSorter};
+    use convert::{self, ConvertConfig, Format};
+    use filter::{self, FilterConfig, Expr};
+    use index_stats::{self, IndexConfig};
+    use config_file::RunFile;
+    use provenance;
+    use md5;
+    use std::time::{UNIX_EPOCH, Duration};
+    use overlap_stats::OverlapStats;
+    use graph::{GraphConfig, StringGraph, GraphRead, Arc as GraphArc};
+    use std::collections::HashMap;
     use useful::Orientation;
-    use std::io::{BufReader, BufRead};
+    use std::io::{BufReader, BufRead, BufWriter, Write, Read};
 
     #[derive (Eq, PartialEq, Hash, Debug)]
     struct GoodSolution{
@@ -36,16 +57,22 @@ mod tests {
             output  :       "./test_output/basic_mapping.txt".to_owned(),
             err_rate :      0.03,
             thresh :        8,
+            max_errors :    None,
             worker_threads: 1,
             reversals :     false,
             inclusions :    false,
             edit_distance : false,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
             verbosity :     0,
             greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
             track_progress :false,
             print:          false,
             n_alphabet:     false,
             format_line:    false,
+            provenance:     false,
         };
         let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
         assert_eq!(2, maps.num_ids());
@@ -63,16 +90,22 @@ mod tests {
             output  :       "./test_output/ham.txt".to_owned(),
             err_rate :      0.02,
             thresh :        4,
+            max_errors :    None,
             worker_threads: 1,
             reversals :     false,
             inclusions :    false,
             edit_distance : false,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
             verbosity :     0,
             greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
             track_progress :false,
             print:          false,
             n_alphabet:     false,
             format_line:    false,
+            provenance:     false,
         };
         let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
         let mode = modes::default_mode();
@@ -90,16 +123,22 @@ mod tests {
             output  :       "./test_output/edit.txt".to_owned(),
             err_rate :      0.2,
             thresh :        5,
+            max_errors :    None,
             worker_threads: 1,
             reversals :     false,
             inclusions :    false,
             edit_distance :     true,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
             verbosity :     0,
             greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
             track_progress :false,
             print:          false,
             n_alphabet:     false,
             format_line:    false,
+            provenance:     false,
         };
         let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
         let mode = modes::default_mode();
@@ -117,16 +156,22 @@ mod tests {
             output  :       "./test_output/ham_rev.txt".to_owned(),
             err_rate :      0.02,
             thresh :        5,
+            max_errors :    None,
             worker_threads: 1,
             reversals :         true,
             inclusions :    false,
             edit_distance : false,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
             verbosity :     0,
             greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
             track_progress :false,
             print:          false,
             n_alphabet:     false,
             format_line:    false,
+            provenance:     false,
         };
         let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
         let mode = modes::default_mode();
@@ -144,16 +189,22 @@ mod tests {
             output  :       "./test_output/ham_incl.txt".to_owned(),
             err_rate :      0.02,
             thresh :        6,
+            max_errors :    None,
             worker_threads: 1,
             reversals :     false,
             inclusions :        true,
             edit_distance : false,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
             verbosity :     0,
             greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
             track_progress :false,
             print:          false,
             n_alphabet:     false,
             format_line:    false,
+            provenance:     false,
         };
         let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
         let mode = modes::default_mode();
@@ -171,16 +222,22 @@ mod tests {
             output  :       "./test_output/ham_no_n.txt".to_owned(),
             err_rate :      0.02,
             thresh :        5,
+            max_errors :    None,
             worker_threads: 1,
             reversals :     false,
             inclusions :    false,
             edit_distance : false,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
             verbosity :     0,
             greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
             track_progress :false,
             print:          false,
             n_alphabet:     false,
             format_line:    false,
+            provenance:     false,
         };
         let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
         let mode = modes::default_mode();
@@ -198,16 +255,22 @@ mod tests {
             output  :       "./test_output/ham_rev_incl.txt".to_owned(),
             err_rate :      0.02,
             thresh :        5,
+            max_errors :    None,
             worker_threads: 1,
             reversals :         true,
             inclusions :        true,
             edit_distance : false,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
             verbosity :     0,
             greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
             track_progress :false,
             print:          false,
             n_alphabet:     false,
             format_line:    false,
+            provenance:     false,
         };
         let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
         let mode = modes::default_mode();
@@ -225,16 +288,22 @@ mod tests {
             output  :       "./test_output/edit_rev.txt".to_owned(),
             err_rate :      0.18,
             thresh :        7,
+            max_errors :    None,
             worker_threads: 1,
             reversals :         true,
             inclusions :    false,
             edit_distance :     true,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
             verbosity :     0,
             greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
             track_progress :false,
             print:          false,
             n_alphabet:     false,
             format_line:    false,
+            provenance:     false,
         };
         let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
         let mode = modes::default_mode();
@@ -252,16 +321,22 @@ mod tests {
             output  :       "./test_output/edit_incl.txt".to_owned(),
             err_rate :      0.17,
             thresh :        6,
+            max_errors :    None,
             worker_threads: 1,
             reversals :     false,
             inclusions :        true,
             edit_distance :     true,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
             verbosity :     0,
             greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
             track_progress :false,
             print:          false,
             n_alphabet:     false,
             format_line:    false,
+            provenance:     false,
         };
         let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
         let mode = modes::default_mode();
@@ -279,16 +354,22 @@ mod tests {
             output  :       "./test_output/edit_rev_incl.txt".to_owned(),
             err_rate :      0.21,
             thresh :        5,
+            max_errors :    None,
             worker_threads: 1,
             reversals :         true,
             inclusions :        true,
             edit_distance :     true,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
             verbosity :     0,
             greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
             track_progress :false,
             print:          false,
             n_alphabet:     false,
             format_line:    false,
+            provenance:     false,
         };
         let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
         let mode = modes::default_mode();
@@ -306,16 +387,22 @@ mod tests {
             output  :       "./test_output/many_errors.txt".to_owned(),
             err_rate :      0.4,
             thresh :        8,
+            max_errors :    None,
             worker_threads: 1,
             reversals :     false,
             inclusions :    false,
             edit_distance : false,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
             verbosity :     0,
             greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
             track_progress :false,
             print:          false,
             n_alphabet:     false,
             format_line:    false,
+            provenance:     false,
 
         };
         let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
@@ -327,6 +414,39 @@ mod tests {
         panic_if_solutions_missing(results, should_contain);
     }
 
+    #[test]
+    fn many_errors_kerrors() {
+        let mode = modes::get_mode("kerrors_4");
+        let config = Config{
+            input  :        "./test_input/many_errors.fasta".to_owned(),
+            output  :       "./test_output/many_errors_kerrors.txt".to_owned(),
+            err_rate :      0.0,
+            thresh :        10,
+            max_errors :    mode.get_max_errors(),
+            worker_threads: 1,
+            reversals :     false,
+            inclusions :    false,
+            edit_distance : false,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
+            verbosity :     0,
+            greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
+            track_progress :false,
+            print:          false,
+            n_alphabet:     false,
+            format_line:    false,
+            provenance:     false,
+        };
+        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+        solve(&config, &maps, mode);
+        let results = read_output(&config.output);
+        let mut should_contain : HashSet<GoodSolution> = HashSet::new();
+        should_contain.insert(GoodSolution{a_nm:"x".to_owned(), b_nm:"y".to_owned(), or:Normal, oha:0, ohb:0, ola:10, olb:10, err:4});
+        panic_if_solutions_missing(results, should_contain);
+    }
+
     #[test]
     fn modified_levenshtein() {
         use verification::modified_levenshtein;
@@ -344,6 +464,23 @@ mod tests {
         assert_eq!(modified_levenshtein(b"GG", b"G"), std::u32::MAX);
     }
 
+    #[test]
+    fn affine_score() {
+        use verification::affine_score;
+        use structs::run_config::Scoring;
+
+        let unit = Scoring{substitution : 1, gap_open : 0, gap_extend : 1, max_score_rate : 0.0};
+        for &(a, b) in [(&b"AA"[..], &b"ATA"[..]), (b"AAAAA", b"CAAAC"), (b"TTTTA", b"TTTT"), (b"ACGTTTTTACG", b"ACGACG")].iter() {
+            assert_eq!(affine_score(a, b, &unit).unwrap().0, verification::modified_levenshtein(a, b));
+        }
+        let affine = Scoring{substitution : 1, gap_open : 3, gap_extend : 1, max_score_rate : 0.0};
+        assert_eq!(affine_score(b"", b"", &affine), Some((0, 1.0)));
+        assert_eq!(affine_score(b"AN", b"AN", &affine), Some((1, 0.5)));
+        assert_eq!(affine_score(b"GG", b"G", &affine), None);
+        assert_eq!(affine_score(b"ACGTTTTTACG", b"ACGACG", &affine), Some((8, 6.0/11.0)));
+        assert_eq!(affine_score(b"ACGTACG", b"ACGAACG", &affine), Some((1, 6.0/7.0)));
+    }
+
     #[test]
     fn filter_correct() {
         let mode = modes::default_mode();
@@ -374,6 +511,962 @@ mod tests {
         }
     }
 
+    #[test]
+    fn filter_correct_kerrors() {
+        for k in 0..5 {
+            for s_param in 1..4 {
+                let mode = modes::get_mode(&format!("kerrors_{}_{}", k, s_param));
+                let guaranteed_extra_blocks = mode.get_guaranteed_extra_blocks();
+                let max_allowed_err = mode.get_max_errors().unwrap();
+                assert!(mode.check_settings(0.0, 2*k+s_param).is_err());
+                assert!(mode.check_settings(0.0, 2*k+s_param+1).is_ok());
+                for patt_len in 5..200 {
+                    for thresh in (2*k+s_param+1)..(patt_len as f32 * 0.5) as i32 {
+                        let blocks_lengths = mode.get_block_lengths(patt_len, 0.0, thresh);
+                        assert!(!(blocks_lengths.contains(&0)));
+                        let mut block_id_lookup = search::get_block_id_lookup(&blocks_lengths);
+                        block_id_lookup.reverse();
+                        for pref_len in thresh..patt_len+1{
+                            let pref_blocks = block_id_lookup[(pref_len-1) as usize] + 1;
+                            if !(pref_blocks >= max_allowed_err + guaranteed_extra_blocks) {
+                                panic!("\nfilter not lenient enough for {} patt_len {} thresh {} pref_len {}.\n\
+                                Block lens is {:?}. pref in {} blocks, permitted {} errors.\n",
+                                mode, patt_len, thresh, pref_len, &blocks_lengths, pref_blocks, max_allowed_err);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn filter_correct_optimal() {
+        for s_param in 1..3 {
+            let mode = modes::get_mode(&format!("optimal_{}", s_param));
+            let guaranteed_extra_blocks = mode.get_guaranteed_extra_blocks();
+            // overlaps of 4 symbols permit 3 errors, so need 3+S blocks
+            assert_eq!(mode.check_settings(0.9, 4).is_err(), s_param > 1);
+            for patt_len in 5..350 {
+                let err_iter = ErrIterator{next:0.5};
+                for err_rate in err_iter {
+                    for thresh in 4..(patt_len as f32 * 0.5) as i32 {
+                        assert!(mode.check_settings(err_rate, thresh).is_ok());
+                        let blocks_lengths = mode.get_block_lengths(patt_len, err_rate, thresh);
+                        assert!(!(blocks_lengths.contains(&0)));
+                        assert_eq!(blocks_lengths, mode.get_block_lengths(patt_len, err_rate, thresh));
+                        let mut block_id_lookup = search::get_block_id_lookup(&blocks_lengths);
+                        block_id_lookup.reverse();
+                        for pref_len in thresh..patt_len+1{
+                            let pref_blocks = block_id_lookup[(pref_len-1) as usize] + 1;
+                            let max_allowed_err = (pref_len as f32 * err_rate).floor() as i32;
+                            if !(pref_blocks >= max_allowed_err + guaranteed_extra_blocks) {
+                                panic!("\nfilter not lenient enough for {} patt_len {} err_rate {} \
+                                thresh {} pref_len {}.\nBlock lens is {:?}. pref in {} blocks, permitted {} errors.\n",
+                                mode, patt_len, err_rate, thresh, pref_len, &blocks_lengths, pref_blocks, max_allowed_err);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lossless_modes() {
+        let ranges = CheckRanges {
+            patt_lens : (10, 60),
+            err_rates : vec![0.0, 0.02, 0.05, 0.1],
+            threshes : (5, 20),
+        };
+        for mode_name in &["kucherov", "kucherov_3", "valimaki", "kerrors_2", "kerrors_0_2", "scheme", "optimal"] {
+            let mode = modes::get_mode(mode_name);
+            if let Err(counterexample) = lossless::check_mode(&*mode, &ranges) {
+                panic!("{} misses overlaps for {}", mode, counterexample);
+            }
+        }
+        // overlaps ending within the first block of their filter are never candidates with S=1
+        let mode = modes::get_mode("kucherov_1");
+        assert!(lossless::check_mode(&*mode, &ranges).is_err());
+    }
+
+    #[test]
+    fn mode_registry() {
+        assert_eq!(format!("{}", modes::default_mode()), "Kucherov S=2");
+        assert_eq!(format!("{}", modes::get_mode("kucherov")), "Kucherov S=2");
+        assert_eq!(format!("{}", modes::get_mode("kucherov_3")), "Kucherov S=3");
+        assert_eq!(format!("{}", modes::get_mode("kucherov:s=3")), "Kucherov S=3");
+        assert_eq!(format!("{}", modes::get_mode("scheme:depth=3")), "Search schemes S=2 depth=3");
+        assert_eq!(format!("{}", modes::get_mode("kerrors:s=2,k=4")), "K-Errors K=4 S=2");
+        for bad in &["nonsense", "kucherov_0", "kucherov_x", "kucherov_2_2", "kucherov:t=2",
+                     "kucherov:s", "kucherov:s=2,s=3", "kerrors", "valimaki_1"] {
+            assert!(modes::parse_mode(bad).is_err(), "mode '{}' should be rejected", bad);
+        }
+        for info in modes::REGISTRY.iter() {
+            assert!(modes::describe_modes().contains(info.name));
+        }
+    }
+
+    #[test]
+    fn bidirectional_index() {
+        use bio::alphabets::Alphabet;
+        use bio::data_structures::bwt::{bwt, less, Occ};
+        use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+        use bio::data_structures::suffix_array::suffix_array;
+        use bidirectional::{BiFMIndex, reversed_text};
+
+        let config = Config{
+            input  :        "./test_input/small_dataset.fasta".to_owned(),
+            output  :       "./test_output/bidirectional_index.txt".to_owned(),
+            err_rate :      0.0,
+            thresh :        4,
+            max_errors :    None,
+            worker_threads: 1,
+            reversals :     true,
+            inclusions :    false,
+            edit_distance : false,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
+            verbosity :     0,
+            greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
+            track_progress :false,
+            print:          false,
+            n_alphabet:     false,
+            format_line:    false,
+            provenance:     false,
+        };
+        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+        let alphabet = Alphabet::new(config.alphabet());
+        let sa = suffix_array(&maps.text);
+        let fwd_bwt = bwt(&maps.text, &sa);
+        let fwd_less = less(&fwd_bwt, &alphabet);
+        let fwd_occ = Occ::new(&fwd_bwt, 3, &alphabet);
+        let fm = FMIndex::new(&fwd_bwt, &fwd_less, &fwd_occ);
+        let rev_text = reversed_text(&maps.text);
+        let rev_sa = suffix_array(&rev_text);
+        let rev_bwt = bwt(&rev_text, &rev_sa);
+        let rev_less = less(&rev_bwt, &alphabet);
+        let rev_occ = Occ::new(&rev_bwt, 3, &alphabet);
+        let rev_fm = FMIndex::new(&rev_bwt, &rev_less, &rev_occ);
+        let bi_fm = BiFMIndex::new(&fm, &rev_fm, &config);
+
+        for id in 0..maps.num_ids() {
+            let string = maps.get_string(id);
+            for start in 0..string.len() {
+                for end in (start+1)..string.len()+1 {
+                    let x = &string[start..end];
+                    let expected = fm.backward_search(x.iter());
+                    // grow the match outwards from a middle character in both directions
+                    let mid = (end - start) / 2;
+                    let mut interval = bi_fm.full_interval();
+                    interval = bi_fm.extend_left(&interval, x[mid]);
+                    for i in (mid+1)..x.len() {
+                        interval = bi_fm.extend_right(&interval, x[i]);
+                    }
+                    for i in (0..mid).rev() {
+                        interval = bi_fm.extend_left(&interval, x[i]);
+                    }
+                    assert_eq!(interval.size, expected.upper - expected.lower);
+                    assert_eq!(interval.forward().lower, expected.lower);
+                    let rev_x : Vec<u8> = x.iter().rev().map(|c| *c).collect();
+                    let expected_rev = rev_fm.backward_search(rev_x.iter());
+                    assert_eq!(interval.lower_rev, expected_rev.lower);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scheme_lossless() {
+        // every error distribution over completely-matched blocks accepted by the unidirectional
+        // filter must also be accepted by some search of the scheme
+        for s_param in 1..4 {
+            let mode = modes::get_mode(&format!("scheme_{}_3", s_param));
+            for patt_blocks in 2..9 {
+                for filter_blocks in 2..(patt_blocks+1) {
+                    let searches = match mode.get_searches(filter_blocks, patt_blocks) {
+                        Some(x) => x,
+                        None => continue,
+                    };
+                    let f = |c : i32| mode.filter_func(c, patt_blocks, patt_blocks - filter_blocks);
+                    for completed in 1..(filter_blocks+1) {
+                        let mut dist = vec![0; completed as usize];
+                        loop {
+                            let mut unidirectional = true;
+                            let mut total = 0;
+                            for c in 0..completed {
+                                total += dist[c as usize];
+                                unidirectional &= total <= f(c);
+                            }
+                            if unidirectional {
+                                let covered = searches.iter().any(|search| {
+                                    if search.start_block() >= completed {
+                                        return false;
+                                    }
+                                    let mut cumulative = 0;
+                                    for (step, &block) in search.order.iter().enumerate() {
+                                        if block >= completed {
+                                            break;
+                                        }
+                                        cumulative += dist[block as usize];
+                                        if cumulative < search.lower[step] || cumulative > search.upper[step] {
+                                            return false;
+                                        }
+                                    }
+                                    true
+                                });
+                                assert!(covered, "{} misses distribution {:?} for {} of {} blocks. Searches {:?}",
+                                        mode, &dist, filter_blocks, patt_blocks, &searches);
+                            }
+                            // next distribution with each block having 0..=2 errors
+                            let mut i = 0;
+                            while i < dist.len() && dist[i] == 2 {
+                                dist[i] = 0;
+                                i += 1;
+                            }
+                            if i == dist.len() {
+                                break;
+                            }
+                            dist[i] += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scheme_matches_kucherov() {
+        let inputs = ["ham", "edit", "ham_rev", "ham_incl", "edit_rev", "edit_incl",
+                      "edit_rev_incl", "many_errors", "small_dataset"];
+        for input in inputs.iter() {
+            for &(err_rate, edit_distance) in [(0.1, false), (0.25, false), (0.1, true), (0.25, true)].iter() {
+                for &(reversals, inclusions) in [(false, false), (true, false), (false, true), (true, true)].iter() {
+                    let mut outputs = Vec::new();
+                    for mode_name in ["kucherov_2", "scheme_2_3"].iter() {
+                        let config = Config{
+                            input  :        format!("./test_input/{}.fasta", input),
+                            output  :       format!("./test_output/scheme_{}_{}.txt", input, mode_name),
+                            err_rate :      err_rate,
+                            thresh :        4,
+                            max_errors :    None,
+                            worker_threads: 1,
+                            reversals :     reversals,
+                            inclusions :    inclusions,
+                            edit_distance : edit_distance,
+                            scoring :       None,
+                            stats_json :    None,
+                            graph :         None,
+                            verbosity :     0,
+                            greedy_output:  false,
+                            sort_memory :   1024 * 1024 * 1024,
+                            track_progress :false,
+                            print:          false,
+                            n_alphabet:     true,
+                            format_line:    false,
+                            provenance:     false,
+                        };
+                        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+                        let stats = solve(&config, &maps, modes::get_mode(mode_name));
+                        // the reversed index is timed on its own
+                        let reversed = stats.stages.iter().filter(|&&(name, _)| name.starts_with("reversed ")).count();
+                        assert_eq!(reversed, if *mode_name == "scheme_2_3" {2} else {0});
+                        outputs.push(read_output(&config.output));
+                    }
+                    assert_eq!(outputs[0], outputs[1], "different solutions for {} with err_rate {} edit {} reversals {} inclusions {}",
+                               input, err_rate, edit_distance, reversals, inclusions);
+                }
+            }
+        }
+    }
+
+    // random reads sampled from a random genome, with random substitutions (and indels, if edit_distance)
+    fn random_reads(seed : usize, edit_distance : bool, reversals : bool) -> Vec<(String, Vec<u8>)> {
+        let mut rng : StdRng = SeedableRng::from_seed(&[seed][..]);
+        let alphabet = b"ACGT";
+        let genome : Vec<u8> = (0..90).map(|_| alphabet[rng.gen_range(0, 4)]).collect();
+        let mut reads = Vec::new();
+        for i in 0..8 {
+            let len = rng.gen_range(12, 30);
+            let start = rng.gen_range(0, genome.len() - len);
+            let mut read : Vec<u8> = Vec::new();
+            for &c in genome[start..start+len].iter() {
+                match rng.gen_range(0, 40) {
+                    0 => read.push(alphabet[rng.gen_range(0, 4)]),
+                    1 if edit_distance => (),
+                    2 if edit_distance => {read.push(c); read.push(alphabet[rng.gen_range(0, 4)]);},
+                    _ => read.push(c),
+                }
+            }
+            if reversals && rng.gen() {
+                read = prepare::reverse_complement(&read);
+            }
+            reads.push((format!("r{:02}", i), read));
+        }
+        reads
+    }
+
+    #[test]
+    fn matches_oracle() {
+        let mode_names = ["kucherov_2", "kucherov_3", "valimaki", "kerrors_2", "scheme", "optimal"];
+        for seed in 0..6 {
+            for &edit_distance in [false, true].iter() {
+                for &(reversals, inclusions) in [(false, false), (true, false), (false, true), (true, true)].iter() {
+                    let reads = random_reads(seed, edit_distance, reversals);
+                    let input = format!("./test_output/oracle_{}_{}.fasta", seed, edit_distance);
+                    {
+                        let mut f = File::create(&input).unwrap();
+                        for &(ref name, ref read) in reads.iter() {
+                            write!(f, ">{}\n{}\n", name, String::from_utf8_lossy(read)).unwrap();
+                        }
+                    }
+                    for mode_name in mode_names.iter() {
+                        let mode = modes::get_mode(mode_name);
+                        let config = Config{
+                            err_rate :      0.1,
+                            thresh :        8,
+                            max_errors :    mode.get_max_errors(),
+                            worker_threads: 1,
+                            reversals :     reversals,
+                            inclusions :    inclusions,
+                            edit_distance : edit_distance,
+                            ..test_config(&input, &format!("./test_output/oracle_{}_{}_{}.txt", seed, edit_distance, mode_name))
+                        };
+                        let expected = oracle::find_overlaps(&reads, &config);
+                        let alignments = oracle::find_alignments(&reads, &config);
+                        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+                        solve(&config, &maps, mode);
+                        let found : HashSet<oracle::Overlap> = tsv::read_overlaps(&config.output).unwrap().into_iter()
+                            .map(|o| oracle::Overlap{a_name:o.name_a, b_name:o.name_b, orientation:o.orientation,
+                                overhang_left_a:o.overhang_left_a, overhang_right_b:o.overhang_right_b,
+                                overlap_a:o.overlap_a, overlap_b:o.overlap_b, errors:o.errors})
+                            .collect();
+                        let invalid : Vec<_> = found.difference(&alignments).collect();
+                        let results = oracle::canonical(&found, &alignments);
+                        if !invalid.is_empty() || expected != results {
+                            panic!("{} differs from the oracle for seed {} edit {} reversals {} inclusions {}.\n\
+                                    missing {:#?}\nunexpected {:#?}\ninvalid {:#?}", mode_name, seed, edit_distance, reversals, inclusions,
+                                   expected.difference(&results).collect::<Vec<_>>(), results.difference(&expected).collect::<Vec<_>>(), invalid);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn simulated_truth() {
+        // without errors, the solver finds exactly the true overlaps of simulated reads
+        let sim = SimConfig {
+            reads_path :    "./test_output/simulated.fasta".to_owned(),
+            truth_path :    "./test_output/simulated_truth.txt".to_owned(),
+            genome_path :   None,
+            genome_len :    3000,
+            num_reads :     150,
+            lengths :       LengthDist::Uniform(40, 80),
+            sub_rate :      0.0,
+            ins_rate :      0.0,
+            del_rate :      0.0,
+            n_rate :        0.0,
+            rev_rate :      0.5,
+            thresh :        25,
+            seed :          7,
+        };
+        let (_, overlaps) = simulate::simulate(&sim).unwrap();
+        assert!(overlaps > 0);
+        let config = Config{
+            input  :        sim.reads_path.clone(),
+            output  :       "./test_output/simulated.txt".to_owned(),
+            err_rate :      0.0,
+            thresh :        25,
+            max_errors :    None,
+            worker_threads: 1,
+            reversals :     true,
+            inclusions :    true,
+            edit_distance : false,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
+            verbosity :     0,
+            greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
+            track_progress :false,
+            print:          false,
+            n_alphabet:     false,
+            format_line:    false,
+            provenance:     false,
+        };
+        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+        solve(&config, &maps, modes::default_mode());
+        assert_eq!(read_output(&config.output), read_output(&sim.truth_path));
+    }
+
+    #[test]
+    fn run_stats() {
+        let config = Config{
+            input  :        "./test_input/edit_rev_incl.fasta".to_owned(),
+            output  :       "./test_output/run_stats.txt".to_owned(),
+            err_rate :      0.1,
+            thresh :        10,
+            max_errors :    None,
+            worker_threads: 2,
+            reversals :     true,
+            inclusions :    true,
+            edit_distance : true,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
+            verbosity :     0,
+            greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
+            track_progress :false,
+            print:          false,
+            n_alphabet:     false,
+            format_line:    false,
+            provenance:     false,
+        };
+        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+        let stats = solve(&config, &maps, modes::default_mode());
+        assert_eq!(stats.solutions, read_output(&config.output).len());
+        assert!(stats.search_nodes > 0);
+        assert!(stats.candidates_generated >= stats.candidates_verified);
+        assert!(stats.candidates_verified >= stats.candidates_passed);
+        assert!(stats.candidates_passed >= stats.solutions);
+        assert_eq!(stats.filter_nodes.iter().sum::<usize>(), stats.search_nodes);
+        assert_eq!(stats.generated_per_errors.iter().sum::<usize>(), stats.candidates_generated);
+        assert!(stats.worst_reads.len() <= run_stats::WORST_READS);
+        assert!(stats.worst_reads.windows(2).all(|w| w[0].2.ratio() >= w[1].2.ratio()));
+        let stages : Vec<&str> = stats.stages.iter().map(|&(name, _)| name).collect();
+        assert_eq!(stages, vec!["suffix array", "bwt and occ", "search (wall clock)",
+                                "candidate generation", "verification", "sorting", "writing"]);
+    }
+
+    #[test]
+    fn external_sort() {
+        let sim = SimConfig {
+            num_reads :     100,
+            ..simulated("external_sort", 11)
+        };
+        simulate::simulate(&sim).unwrap();
+        let mut config = Config{
+            format_line:    true,
+            ..test_config(&sim.reads_path, "./test_output/external_sort_in_memory.txt")
+        };
+        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+        let in_memory = solve(&config, &maps, modes::default_mode());
+
+        // room for only a few solutions at a time, forcing many runs to be spilled and merged
+        config.output = "./test_output/external_sort_spilled.txt".to_owned();
+        config.sort_memory = 200;
+        let spilled = solve(&config, &maps, modes::default_mode());
+
+        let lines = |path : &str| -> Vec<String> {
+            BufReader::new(File::open(path).unwrap()).lines().map(|l| l.unwrap()).collect()
+        };
+        assert!(in_memory.solutions > 10);
+        assert_eq!(in_memory.solutions, spilled.solutions);
+        assert_eq!(in_memory.solutions_per_errors, spilled.solutions_per_errors);
+        assert_eq!(lines("./test_output/external_sort_in_memory.txt"), lines("./test_output/external_sort_spilled.txt"));
+        assert!(!std::path::Path::new("./test_output/external_sort_spilled.txt.sort_run_0").exists());
+
+        // one solution per run, and each twice, so that the runs are merged in several passes
+        let solutions = tsv::read_solutions("./test_output/external_sort_in_memory.txt", &maps).unwrap();
+        let mut merged = Vec::new();
+        {
+            let mut sorter = SolutionSorter::new(&maps, 1, "./test_output/external_sort_passes.txt");
+            for sol in solutions.iter().rev().chain(solutions.iter()) {
+                sorter.push(sol.clone()).unwrap();
+            }
+            sorter.finish(|sol| merged.push(sol.clone())).unwrap();
+            assert!(sorter.runs() > external_sort::MAX_FAN_IN * 2);
+        }
+        assert_eq!(merged, solutions);
+        assert!(!std::path::Path::new("./test_output/external_sort_passes.txt.sort_run_0").exists());
+        assert!(!std::path::Path::new(&format!("./test_output/external_sort_passes.txt.sort_run_{}", solutions.len() * 2)).exists());
+    }
+
+    #[test]
+    fn greedy_dedup() {
+        // greedy output has the same solutions as sorted output, each once
+        let sim = SimConfig {
+            num_reads :     100,
+            ..simulated("greedy_dedup", 13)
+        };
+        simulate::simulate(&sim).unwrap();
+        let mut config = Config{
+            worker_threads: 3,
+            ..test_config(&sim.reads_path, "./test_output/greedy_dedup_sorted.txt")
+        };
+        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+        solve(&config, &maps, modes::default_mode());
+        config.output = "./test_output/greedy_dedup_greedy.txt".to_owned();
+        config.greedy_output = true;
+        let stats = solve(&config, &maps, modes::default_mode());
+
+        let lines = |path : &str| -> Vec<String> {
+            let mut lines : Vec<String> = BufReader::new(File::open(path).unwrap()).lines().map(|l| l.unwrap()).collect();
+            lines.sort();
+            lines
+        };
+        let greedy = lines("./test_output/greedy_dedup_greedy.txt");
+        assert!(greedy.len() > 10);
+        assert_eq!(greedy.len(), stats.solutions);
+        assert_eq!(greedy, lines("./test_output/greedy_dedup_sorted.txt"));
+    }
+
+    #[test]
+    fn string_graph() {
+        // error-free reads of a random genome reduce to simple paths
+        let sim = SimConfig {
+            genome_len :    3000,
+            num_reads :     150,
+            sub_rate :      0.0,
+            ins_rate :      0.0,
+            del_rate :      0.0,
+            ..simulated("string_graph", 17)
+        };
+        simulate::simulate(&sim).unwrap();
+        let config = Config{
+            err_rate :      0.0,
+            edit_distance : false,
+            graph :         Some(GraphConfig{
+                gfa_path :      Some("./test_output/string_graph.gfa".to_owned()),
+                unitigs_path :  Some("./test_output/string_graph_unitigs.fasta".to_owned()),
+                layout_path :   Some("./test_output/string_graph_layout.txt".to_owned()),
+                consensus_path : None,
+                fuzz :          10,
+                weak_ratio :    0.0,
+                max_tip :       0,
+                max_bubble :    0,
+            }),
+            ..test_config(&sim.reads_path, "./test_output/string_graph.txt")
+        };
+        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+        let stats = solve(&config, &maps, modes::default_mode());
+        assert!(stats.stages.iter().any(|&(name, _)| name == "string graph"));
+
+        let flip = |strand : &str| if strand == "+" {"-".to_owned()} else {"+".to_owned()};
+        let mut segments = HashMap::new();
+        let mut out_degrees : HashMap<(String, String), usize> = HashMap::new();
+        for line in BufReader::new(File::open("./test_output/string_graph.gfa").unwrap()).lines() {
+            let line = line.unwrap();
+            let fields : Vec<&str> = line.split('\t').collect();
+            match fields[0] {
+                "S" => {segments.insert(fields[1].to_owned(), fields[2].as_bytes().to_vec());},
+                "L" => {
+                    *out_degrees.entry((fields[1].to_owned(), fields[2].to_owned())).or_insert(0) += 1;
+                    *out_degrees.entry((fields[3].to_owned(), flip(fields[4]))).or_insert(0) += 1;
+                },
+                _ => (),
+            }
+        }
+        // some reads are contained in others
+        assert!(segments.len() > 0 && segments.len() < sim.num_reads);
+        assert!(out_degrees.len() > 10);
+        assert!(out_degrees.values().all(|&d| d == 1));
+
+        // without errors, each read of a unitig is exactly where the layout places it
+        let mut unitigs = HashMap::new();
+        let mut name = String::new();
+        for line in BufReader::new(File::open("./test_output/string_graph_unitigs.fasta").unwrap()).lines() {
+            let line = line.unwrap();
+            if line.starts_with('>') {
+                name = line[1..].split(' ').next().unwrap().to_owned();
+            } else {
+                unitigs.insert(name.clone(), line.into_bytes());
+            }
+        }
+        let mut placed = 0;
+        for line in BufReader::new(File::open("./test_output/string_graph_layout.txt").unwrap()).lines() {
+            let line = line.unwrap();
+            let fields : Vec<&str> = line.split('\t').collect();
+            let (start, end) : (usize, usize) = (fields[3].parse().unwrap(), fields[4].parse().unwrap());
+            let read = &segments[fields[1]];
+            let read = if fields[2] == "+" {read.clone()} else {prepare::reverse_complement(read)};
+            assert_eq!(&unitigs[fields[0]][start..end], &read[..]);
+            placed += 1;
+        }
+        assert_eq!(placed, segments.len());
+        assert!(unitigs.len() < segments.len());
+    }
+
+    #[test]
+    fn graph_cleaning() {
+        // reads 'r0', 'r1', ... of 100 symbols, and the given overlaps (a, b, len, errors) on the forward strands
+        let graph_of = |num_reads : usize, overlaps : &[(usize, usize, usize, u32)]| -> StringGraph {
+            let reads = (0..num_reads)
+                .map(|r| GraphRead{name : format!("r{}", r), seq : vec![b'A'; 100], contained : false, removed : false})
+                .collect();
+            let mut arcs = Vec::new();
+            for &(a, b, len, errors) in overlaps.iter() {
+                arcs.push(GraphArc{from : 2*a, to : 2*b, len : len, overlap_from : 100 - len, overlap_to : 100 - len,
+                                   errors : errors, removed : false});
+                arcs.push(GraphArc{from : 2*b + 1, to : 2*a + 1, len : len, overlap_from : 100 - len, overlap_to : 100 - len,
+                                   errors : errors, removed : false});
+            }
+            StringGraph{reads : reads, arcs : arcs}
+        };
+
+        // r4 is a dead end joining the path r0 -> r3
+        let mut graph = graph_of(5, &[(0, 1, 10, 0), (1, 2, 10, 0), (2, 3, 10, 0), (4, 2, 10, 0)]);
+        assert_eq!(graph.trim_tips(1), 1);
+        assert!(graph.reads[4].removed);
+        assert_eq!(graph.unitigs().len(), 1);
+
+        // r1 and r2 are alternatives between r0 and r3. r2's overlaps have more errors
+        let mut graph = graph_of(5, &[(0, 1, 10, 0), (1, 3, 10, 1), (0, 2, 10, 3), (2, 3, 10, 0), (3, 4, 10, 0)]);
+        assert_eq!(graph.pop_bubbles(2), 1);
+        assert!(graph.reads[2].removed && !graph.reads[1].removed);
+        let unitigs = graph.unitigs();
+        assert_eq!(unitigs.len(), 1);
+        assert_eq!(unitigs[0].reads.len(), 4);
+
+        // r0 overlaps r1 by 90 and r2 by only 50. r2 is also entered from r3
+        let mut graph = graph_of(4, &[(0, 1, 10, 0), (0, 2, 50, 0), (3, 2, 10, 0)]);
+        assert_eq!(graph.remove_weak_overlaps(0.7), 1);
+        assert!(graph.arcs[2].removed && graph.arcs[3].removed);
+        assert_eq!(graph.num_overlaps(), 2);
+
+        // of two reads within one another, the one with the larger name is dropped, whichever is A
+        let mut f = File::create("./test_output/graph_containment.fasta").unwrap();
+        f.write_all(b">r0\nACGTACGTACGTACGTACGT\n>r1\nACGTACGTACGTACGTACGT\n>r2\nACGTACGTACGTACGTACG\n").unwrap();
+        drop(f);
+        let maps = prepare::read_maps("./test_output/graph_containment.fasta", false, true).unwrap();
+        let within = |id_a : usize, id_b : usize, oha : i32, ohb : i32| Solution{
+            id_a : id_a, id_b : id_b, orientation : Normal, overhang_left_a : oha, overhang_right_b : ohb,
+            overlap_a : (20 - oha.abs() - ohb.abs()) as usize, overlap_b : 20, errors : 1, score : None, identity : None,
+        };
+        for solutions in [vec![within(1, 0, 0, 0)], vec![within(0, 1, 0, 0)], vec![within(0, 1, 1, -1), within(0, 1, -1, 1)]].iter() {
+            let graph = StringGraph::new(&maps, solutions, false);
+            assert!(!graph.reads[0].contained && graph.reads[1].contained);
+        }
+    }
+
+    #[test]
+    fn unitig_consensus() {
+        let mut rng : StdRng = SeedableRng::from_seed(&[23][..]);
+        let genome : Vec<u8> = (0..140).map(|_| b"ACGT"[rng.gen_range(0, 4)]).collect();
+        let other = |c : u8| if c == b'A' {b'C'} else {b'A'};
+        // r0 has a substitution at 50 and an insertion before 60. r1 lacks the symbol at 30. r2 is exact
+        let mut r0 = genome[..100].to_vec();
+        r0[50] = other(r0[50]);
+        r0.insert(60, b'G');
+        let mut r1 = genome[20..120].to_vec();
+        r1.remove(10);
+        let r2 = genome[40..140].to_vec();
+        let reads = vec![r0, r1, r2]
+            .into_iter()
+            .enumerate()
+            .map(|(r, seq)| GraphRead{name : format!("r{}", r), seq : seq, contained : false, removed : false})
+            .collect();
+        let arcs = vec![
+            GraphArc{from : 0, to : 2, len : 20, overlap_from : 81, overlap_to : 79, errors : 3, removed : false},
+            GraphArc{from : 3, to : 1, len : 20, overlap_from : 79, overlap_to : 81, errors : 3, removed : false},
+            GraphArc{from : 2, to : 4, len : 20, overlap_from : 80, overlap_to : 80, errors : 0, removed : false},
+            GraphArc{from : 5, to : 3, len : 19, overlap_from : 80, overlap_to : 80, errors : 0, removed : false},
+        ];
+        let graph = StringGraph{reads : reads, arcs : arcs};
+        let unitigs = graph.unitigs();
+        assert_eq!(unitigs.len(), 1);
+        assert_eq!(unitigs[0].reads.len(), 3);
+        assert!(unitigs[0].seq != genome);
+        assert_eq!(consensus::consensus(&graph, &unitigs[0]), genome);
+    }
+
+    #[test]
+    fn long_reads() {
+        // the search is as deep as the overlap is long. this must not depend on the size of the call stack
+        let mut rng : StdRng = SeedableRng::from_seed(&[3][..]);
+        let genome : Vec<u8> = (0..60000).map(|_| b"ACGT"[rng.gen_range(0, 4)]).collect();
+        {
+            let mut f = File::create("./test_output/long_reads.fasta").unwrap();
+            write!(f, ">x\n{}\n>y\n{}\n", String::from_utf8_lossy(&genome[..40000]),
+                   String::from_utf8_lossy(&genome[10000..])).unwrap();
+        }
+        let config = Config{
+            input  :        "./test_output/long_reads.fasta".to_owned(),
+            output  :       "./test_output/long_reads.txt".to_owned(),
+            err_rate :      0.0,
+            thresh :        1000,
+            max_errors :    None,
+            worker_threads: 1,
+            reversals :     false,
+            inclusions :    false,
+            edit_distance : false,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
+            verbosity :     0,
+            greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
+            track_progress :false,
+            print:          false,
+            n_alphabet:     false,
+            format_line:    false,
+            provenance:     false,
+        };
+        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+        solve(&config, &maps, modes::default_mode());
+        let mut should_contain : HashSet<GoodSolution> = HashSet::new();
+        should_contain.insert(GoodSolution{a_nm:"x".to_owned(), b_nm:"y".to_owned(), or:Normal, oha:10000, ohb:20000, ola:30000, olb:30000, err:0});
+        assert_eq!(read_output(&config.output), should_contain);
+    }
+
+    #[test]
+    fn overlap_stats() {
+        // r1 -> r2 -> r3' with r4 inside r1. r5 overlaps nothing
+        let overlaps : Vec<tsv::Overlap> = [
+            "r1\tr2\tN\t30\t30\t70\t70\t1",
+            "r2\tr3\tI\t-20\t-20\t80\t80\t0",
+            "r1\tr4\tN\t10\t-40\t50\t50\t2",
+        ].iter().map(|line| tsv::parse_overlap(line).unwrap()).collect();
+        assert!(tsv::parse_overlap("r1\tr2\tX\t30\t30\t70\t70\t1").is_err());
+        let names = vec!["r1", "r2", "r3", "r4", "r5"].into_iter().map(|x| x.to_owned()).collect();
+        let stats = OverlapStats::new(&overlaps, Some(names));
+        assert_eq!((stats.reads, stats.overlaps, stats.containments, stats.contained_reads), (5, 3, 1, 1));
+        assert_eq!(stats.singletons, Some(1));
+        assert_eq!(stats.components, vec![4, 1]);
+        // r2's begin meets r1's end and r3. r3 is reversed, so it's r3's begin that meets r2
+        assert_eq!(stats.begin_degrees, vec![3, 1, 1]);
+        assert_eq!(stats.end_degrees, vec![4, 1]);
+        assert_eq!(stats.overlap_lengths, vec![0, 0, 0, 0, 0, 1, 2]);
+        assert_eq!(stats.errors, vec![1, 1, 1]);
+
+        // without the reads, r5 is unknown
+        let stats = OverlapStats::new(&overlaps, None);
+        assert_eq!((stats.reads, stats.singletons), (4, None));
+    }
+
+    #[test]
+    fn tsv_solutions() {
+        // solutions read back from the output are written out again unchanged
+        let sim = simulated("tsv_solutions", 5);
+        simulate::simulate(&sim).unwrap();
+        let mut config = Config{
+            format_line:    true,
+            ..test_config(&sim.reads_path, "./test_output/tsv_solutions.txt")
+        };
+        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+        for &scoring in [false, true].iter() {
+            if scoring {
+                config.scoring = Some(Scoring{substitution : 2, gap_open : 3, gap_extend : 1, max_score_rate : 0.1});
+            }
+            let stats = solve(&config, &maps, modes::default_mode());
+            let solutions = tsv::read_solutions(&config.output, &maps).unwrap();
+            assert!(solutions.len() > 10);
+            assert_eq!(solutions.len(), stats.solutions);
+            assert!(solutions.iter().all(|s| s.id_a % 2 == 0 && s.id_b % 2 == 0 && s.score.is_some() == scoring));
+            // a score within its limit doesn't let an overlap exceed the error rate
+            assert!(solutions.iter().all(|s| s.errors as i32 <= config.max_errors_for(std::cmp::max(s.overlap_a, s.overlap_b))));
+
+            let rewritten = "./test_output/tsv_solutions_rewritten.txt";
+            {
+                let mut buf = BufWriter::new(File::create(rewritten).unwrap());
+                for sol in solutions.iter() {
+                    write_solution(&mut buf, sol, &maps, &config);
+                }
+            }
+            let lines = |path : &str| -> Vec<String> {
+                BufReader::new(File::open(path).unwrap()).lines().map(|l| l.unwrap()).collect()
+            };
+            assert_eq!(&lines(&config.output)[1..], &lines(rewritten)[..]);
+        }
+
+        // reads that are not in the input are an error
+        let mut f = File::create("./test_output/tsv_solutions_unknown.txt").unwrap();
+        f.write_all(b"r0000\tnot_a_read\tN\t10\t10\t30\t30\t0\n").unwrap();
+        assert!(tsv::read_solutions("./test_output/tsv_solutions_unknown.txt", &maps).is_err());
+    }
+
+    #[test]
+    fn provenance() {
+        // the provenance comments are read back, and don't disturb the overlaps after them
+        let sim = SimConfig {
+            genome_len :    1000,
+            num_reads :     40,
+            lengths :       LengthDist::Fixed(50),
+            sub_rate :      0.0,
+            ins_rate :      0.0,
+            del_rate :      0.0,
+            ..simulated("provenance", 2)
+        };
+        simulate::simulate(&sim).unwrap();
+        let config = Config{
+            err_rate :      0.0,
+            inclusions :    false,
+            edit_distance : false,
+            n_alphabet:     true,
+            format_line:    true,
+            provenance:     true,
+            ..test_config(&sim.reads_path, "./test_output/provenance.txt")
+        };
+        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+        let stats = solve(&config, &maps, modes::default_mode());
+        let comments = tsv::read_comments(&config.output).unwrap();
+        assert_eq!(comments.len(), 5);
+        assert!(comments[0].starts_with("version: rust-overlaps "));
+        let mut fasta = Vec::new();
+        File::open(&config.input).unwrap().read_to_end(&mut fasta).unwrap();
+        assert_eq!(comments[2], format!("input: {} md5 {:x} reads 40 bases 2000", config.input, md5::compute(&fasta)));
+        assert_eq!(comments[3], format!("mode: {}", modes::default_mode()));
+        assert_eq!(comments[4], format!("config: {:?}", config));
+        assert_eq!(tsv::read_solutions(&config.output, &maps).unwrap().len(), stats.solutions);
+
+        assert_eq!(provenance::utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(provenance::utc_timestamp(UNIX_EPOCH + Duration::from_secs(951782400 + 3723)), "2000-02-29T01:02:03Z");
+        assert_eq!(provenance::utc_timestamp(UNIX_EPOCH + Duration::from_secs(1496318400)), "2017-06-01T12:00:00Z");
+    }
+
+    #[test]
+    fn convert_formats() {
+        // overlaps converted to each format and back are unchanged
+        // with N symbols, which the run removes (-n)
+        let sim = SimConfig {
+            n_rate :        0.01,
+            ..simulated("convert", 9)
+        };
+        simulate::simulate(&sim).unwrap();
+        let config = test_config(&sim.reads_path, "./test_output/convert.tsv");
+        let maps = prepare::read_and_prepare(&config.input, &config).expect("Couldn't interpret data.");
+        let stats = solve(&config, &maps, modes::default_mode());
+        assert!(stats.solutions > 10);
+
+        let lines = |path : &str| -> Vec<String> {
+            BufReader::new(File::open(path).unwrap()).lines().map(|l| l.unwrap()).collect()
+        };
+        for &format in [Format::Tsv, Format::Paf, Format::Gfa, Format::Asqg, Format::Mhap].iter() {
+            let there = ConvertConfig {
+                input :     config.output.clone(),
+                output :    "./test_output/convert_there".to_owned(),
+                reads :     sim.reads_path.clone(),
+                from :      Format::Tsv,
+                to :        format,
+                n_alphabet : config.n_alphabet,
+            };
+            assert_eq!(convert::convert(&there).unwrap(), (stats.solutions, 0));
+            let back = ConvertConfig {
+                input :     there.output.clone(),
+                output :    "./test_output/convert_back.tsv".to_owned(),
+                reads :     sim.reads_path.clone(),
+                from :      format,
+                to :        Format::Tsv,
+                n_alphabet : config.n_alphabet,
+            };
+            assert_eq!(convert::convert(&back).unwrap(), (stats.solutions, 0));
+            assert_eq!(lines(&config.output), lines(&back.output), "{} changed the overlaps", format);
+        }
+
+        // a hit within both reads is skipped, a dovetail kept
+        let (len_a, len_b) = (maps.get_length(0), maps.get_length(2));
+        let mut f = File::create("./test_output/convert_hits.paf").unwrap();
+        write!(f, "{}\t{}\t5\t25\t+\t{}\t{}\t10\t30\t20\t20\t255\n", maps.get_name_for(0), len_a, maps.get_name_for(2), len_b).unwrap();
+        write!(f, "{}\t{}\t{}\t{}\t+\t{}\t{}\t0\t20\t20\t20\t255\n", maps.get_name_for(0), len_a, len_a - 20, len_a,
+               maps.get_name_for(2), len_b).unwrap();
+        drop(f);
+        let (solutions, skipped) = convert::read_solutions("./test_output/convert_hits.paf", Format::Paf, &maps).unwrap();
+        assert_eq!((solutions.len(), skipped), (1, 1));
+        assert_eq!((solutions[0].overlap_a, solutions[0].overlap_b, solutions[0].errors), (20, 20, 0));
+
+        assert_eq!(Format::parse(None, "x.paf"), Ok(Format::Paf));
+        assert_eq!(Format::parse(Some("ASQG"), "x.paf"), Ok(Format::Asqg));
+        assert!(Format::parse(None, "x").is_err());
+    }
+
+    #[test]
+    fn filter_overlaps() {
+        let input = "./test_output/filter_in.tsv";
+        let mut f = File::create(input).unwrap();
+        f.write_all(b"idA\tidB\tO\tOHA\tOHB\tOLA\tOLB\tK\n\
+                      r1\tr2\tN\t30\t30\t70\t70\t1\n\
+                      r1\tr3\tI\t50\t40\t50\t51\t3\n\
+                      r2\tr3\tN\t-20\t-20\t80\t80\t0\n\
+                      r1\tr4\tN\t10\t-40\t50\t50\t2\n").unwrap();
+        let names = "./test_output/filter_names.txt";
+        File::create(names).unwrap().write_all(b"r4\n").unwrap();
+        let run = |expression : Option<&str>, max_per_read, keep_reads : bool, drop_reads : bool| -> Vec<String> {
+            let config = FilterConfig {
+                input :         input.to_owned(),
+                output :        "./test_output/filter_out.tsv".to_owned(),
+                expression :    expression.map(|x| Expr::parse(x).unwrap()),
+                max_per_read :  max_per_read,
+                keep_reads :    if keep_reads {Some(names.to_owned())} else {None},
+                drop_reads :    if drop_reads {Some(names.to_owned())} else {None},
+            };
+            let (kept, total) = filter::filter(&config).unwrap();
+            assert_eq!(total, 4);
+            let lines : Vec<String> = BufReader::new(File::open(&config.output).unwrap()).lines().map(|l| l.unwrap()).collect();
+            assert!(lines[0].starts_with("idA"));
+            assert_eq!(kept, lines.len() - 1);
+            lines[1..].iter().map(|l| l.split('\t').take(2).collect::<Vec<_>>().join("-")).collect()
+        };
+        assert_eq!(run(None, None, false, false), vec!["r1-r2", "r1-r3", "r2-r3", "r1-r4"]);
+        assert_eq!(run(Some("OLA >= 60 && K/OLA < 0.05 && O == \"N\""), None, false, false), vec!["r1-r2", "r2-r3"]);
+        assert_eq!(run(Some("!(OHA > 0) || idB == 'r3' && -K + 2 * 2 > 0"), None, false, false), vec!["r1-r3", "r2-r3"]);
+        assert_eq!(run(None, None, false, true), vec!["r1-r2", "r1-r3", "r2-r3"]);
+        assert_eq!(run(None, None, true, false), Vec::<String>::new());
+        // r1 keeps its two longest overlaps. with one each, r1-r2 is kept by r1 but not by r2
+        assert_eq!(run(None, Some(2), false, false), vec!["r1-r2", "r1-r3", "r2-r3"]);
+        assert_eq!(run(None, Some(1), false, false), vec!["r2-r3"]);
+        // overlaps are written while the input is read, so it can't be the output
+        let in_place = FilterConfig{input : input.to_owned(), output : input.to_owned(), expression : None,
+                                    max_per_read : None, keep_reads : None, drop_reads : None};
+        assert!(filter::filter(&in_place).is_err());
+        assert_eq!(BufReader::new(File::open(input).unwrap()).lines().count(), 5);
+
+        for bad in ["OLA", "OLA >", "O < 3", "OLA > 1 && K", "XYZ == 1", "(K > 1", "idA == \"r1", "K > 1 1"].iter() {
+            assert!(Expr::parse(bad).is_err(), "'{}' was accepted", bad);
+        }
+    }
+
+    #[test]
+    fn index_stats() {
+        let index = IndexConfig {
+            input :         "./test_input/basic_mapping.fasta".to_owned(),
+            reversals :     true,
+            n_alphabet :    true,
+        };
+        let stats = index_stats::index_stats(&index, &modes::default_mode()).unwrap();
+        assert_eq!((stats.reads, stats.symbols, stats.n_symbols, stats.shortest, stats.longest), (2, 11, 0, 5, 6));
+        // both strands of both reads, each after a '$', and a '#' at the end
+        assert_eq!(stats.text_len, 2 * (5 + 6) + 4 + 1);
+        assert!(!stats.reversed_index);
+        assert_eq!(stats.bytes.iter().find(|&&(name, _)| name == "suffix array").unwrap().1,
+                   stats.text_len * std::mem::size_of::<usize>());
+
+        let stats = index_stats::index_stats(&index, &modes::parse_mode("scheme_2").unwrap()).unwrap();
+        assert!(stats.reversed_index);
+        assert!(stats.bytes.iter().any(|&(name, _)| name == "reversed occ"));
+        let stages : Vec<&str> = stats.stages.iter().map(|&(name, _)| name).collect();
+        assert_eq!(stages, vec!["parsing", "suffix array", "bwt and occ", "reversed suffix array", "reversed bwt and occ"]);
+    }
+
+    #[test]
+    fn config_file() {
+        let file = RunFile::parse("input = \"reads.fa\"\nerr_rate = 0.02\nthresh = 50\nreversals = true\n\
+                                   inclusions = false\nverbose = 2\nscoring = [1, 4, 1]\n[mode]\nname = \"kerrors\"\nk = 3\n").unwrap();
+        assert_eq!(file.value_of("IN_PATH"), Some("reads.fa"));
+        assert_eq!(file.value_of("ERR_RATE"), Some("0.02"));
+        assert_eq!(file.value_of("THRESH"), Some("50"));
+        assert_eq!(file.value_of("OUT_PATH"), None);
+        assert_eq!(file.value_of("verbose"), Some("2"));
+        assert_eq!(file.value_of("scoring"), Some("1,4,1"));
+        assert!(file.is_set("reversals"));
+        assert!(!file.is_set("inclusions"));
+        let mode = file.value_of("mode").unwrap();
+        assert_eq!(mode, "kerrors:k=3");
+        assert!(modes::parse_mode(mode).is_ok());
+        assert_eq!(RunFile::parse("mode = \"kucherov_3\"").unwrap().value_of("mode"), Some("kucherov_3"));
+
+        assert!(RunFile::parse("reversal = true").is_err());
+        assert!(RunFile::parse("reversals = 1").is_err());
+        assert!(RunFile::parse("verbose = -1").is_err());
+        assert!(RunFile::parse("gfa = true").is_err());
+        assert!(RunFile::parse("[mode]\ns = 3").is_err());
+        assert!(RunFile::parse("thresh = ").is_err());
+    }
+
     struct ErrIterator{
         next : f32,
     }
@@ -391,30 +1484,62 @@ mod tests {
         }
     }
 
-    fn read_output(filename : &str) -> HashSet<GoodSolution>{
-        let mut result : HashSet<GoodSolution> = HashSet::new();
-        let f = File::open(filename).unwrap();
-        let file = BufReader::new(&f);
-        for line in file.lines() {
-            match line{
-                Ok(l) =>  {
-                    let parts = l.split("\t").collect::<Vec<_>>();
-                    let sol = GoodSolution{
-                        a_nm: parts[0].to_owned(),
-                        b_nm: parts[1].to_owned(),
-                        or: if parts[2]=="N" {Orientation::Normal} else {Orientation::Reversed},
-                        oha: parts[3].parse().unwrap(),
-                        ohb: parts[4].parse().unwrap(),
-                        ola: parts[5].parse().unwrap(),
-                        olb: parts[6].parse().unwrap(),
-                        err: parts[7].parse().unwrap(),
-                    };
-                    result.insert(sol);
-                },
-                Err(_) => println!(),
-            };
+    // a run with edit distance, reversals and inclusions, writing the output sorted in memory
+    fn test_config(input : &str, output : &str) -> Config {
+        Config{
+            input  :        input.to_owned(),
+            output  :       output.to_owned(),
+            err_rate :      0.05,
+            thresh :        20,
+            max_errors :    None,
+            worker_threads: 2,
+            reversals :     true,
+            inclusions :    true,
+            edit_distance : true,
+            scoring :       None,
+            stats_json :    None,
+            graph :         None,
+            verbosity :     0,
+            greedy_output:  false,
+            sort_memory :   1024 * 1024 * 1024,
+            track_progress :false,
+            print:          false,
+            n_alphabet:     false,
+            format_line:    false,
+            provenance:     false,
+        }
+    }
+
+    // reads with a few errors of a small random genome, written to ./test_output/NAME.fasta
+    fn simulated(name : &str, seed : usize) -> SimConfig {
+        SimConfig {
+            reads_path :    format!("./test_output/{}.fasta", name),
+            truth_path :    format!("./test_output/{}_truth.txt", name),
+            genome_path :   None,
+            genome_len :    2000,
+            num_reads :     80,
+            lengths :       LengthDist::Uniform(40, 80),
+            sub_rate :      0.01,
+            ins_rate :      0.005,
+            del_rate :      0.005,
+            n_rate :        0.0,
+            rev_rate :      0.5,
+            thresh :        20,
+            seed :          seed,
         }
-        result
+    }
+
+    fn read_output(filename : &str) -> HashSet<GoodSolution>{
+        tsv::read_overlaps(filename).unwrap().into_iter().map(|o| GoodSolution{
+            a_nm: o.name_a,
+            b_nm: o.name_b,
+            or: o.orientation,
+            oha: o.overhang_left_a,
+            ohb: o.overhang_right_b,
+            ola: o.overlap_a,
+            olb: o.overlap_b,
+            err: o.errors,
+        }).collect()
     }
 
     fn panic_if_solutions_missing(solutions : HashSet<GoodSolution>, should_contain : HashSet<GoodSolution>){
@@ -0,0 +1,141 @@
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::fs::File;
+use std::collections::HashMap;
+
+////////////////////////////////////////////////////////////
+
+use useful::Orientation;
+use structs::solutions::Solution;
+use structs::run_config::Maps;
+
+/*
+Reads overlaps in the solver's output format (see write_solution in main.rs) back in.
+The header line written with -f and the comment lines of --provenance are skipped.
+The score and identity columns written with --scoring are optional.
+Overlaps refer to reads by name, so no fasta file is needed to read them.
+Given the Maps of the fasta file, they can instead be read as Solutions for further processing.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlap {
+    pub name_a : String,
+    pub name_b : String,
+    pub orientation : Orientation,
+    pub overhang_left_a : i32,
+    pub overhang_right_b : i32,
+    pub overlap_a : usize,
+    pub overlap_b : usize,
+    pub errors : u32,
+    pub score : Option<u32>,
+    pub identity : Option<f32>,
+}
+
+pub fn parse_overlap(line : &str) -> Result<Overlap, String> {
+    let bad = || format!("Couldn't interpret overlap line '{}'.", line);
+    let fields : Vec<&str> = line.split('\t').collect();
+    if fields.len() != 8 && fields.len() != 10 {
+        return Err(bad());
+    }
+    Ok(Overlap {
+        name_a : fields[0].to_owned(),
+        name_b : fields[1].to_owned(),
+        orientation : match fields[2] {
+            "N" => Orientation::Normal,
+            "I" => Orientation::Reversed,
+            _ => return Err(bad()),
+        },
+        overhang_left_a : fields[3].parse().map_err(|_| bad())?,
+        overhang_right_b : fields[4].parse().map_err(|_| bad())?,
+        overlap_a : fields[5].parse().map_err(|_| bad())?,
+        overlap_b : fields[6].parse().map_err(|_| bad())?,
+        errors : fields[7].parse().map_err(|_| bad())?,
+        score : if fields.len() == 10 {Some(fields[8].parse().map_err(|_| bad())?)} else {None},
+        identity : if fields.len() == 10 {Some(fields[9].parse().map_err(|_| bad())?)} else {None},
+    })
+}
+
+pub fn read_overlaps(path : &str) -> Result<Vec<Overlap>, io::Error> {
+    let f = File::open(path)?;
+    let mut overlaps = Vec::new();
+    let mut first = true;
+    for line in BufReader::new(f).lines() {
+        let line = line?;
+        if line.is_empty() || line.starts_with('#') || (first && line.starts_with("idA\t")) {
+            continue;
+        }
+        first = false;
+        overlaps.push(parse_overlap(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?);
+    }
+    Ok(overlaps)
+}
+
+// the comment lines at the top of a file, without their '#'
+pub fn read_comments(path : &str) -> Result<Vec<String>, io::Error> {
+    let mut comments = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if !line.starts_with('#') {
+            break;
+        }
+        let comment = &line[1..];
+        comments.push(if comment.starts_with(' ') {&comment[1..]} else {comment}.to_owned());
+    }
+    Ok(comments)
+}
+
+/*
+Reads the solutions of an earlier run on the reads of these maps.
+With reversals, a read has two IDs. Solutions refer to the first (A is never reversed, and the
+orientation says whether B is), so that is the one used.
+*/
+pub fn read_solutions(path : &str, maps : &Maps) -> Result<Vec<Solution>, io::Error> {
+    let ids = ids_by_name(maps);
+    let mut solutions = Vec::new();
+    for o in read_overlaps(path)? {
+        solutions.push(Solution {
+            id_a : id_of(&ids, &o.name_a)?,
+            id_b : id_of(&ids, &o.name_b)?,
+            orientation : o.orientation,
+            overhang_left_a : o.overhang_left_a,
+            overhang_right_b : o.overhang_right_b,
+            overlap_a : o.overlap_a,
+            overlap_b : o.overlap_b,
+            errors : o.errors,
+            score : o.score,
+            identity : o.identity,
+        });
+    }
+    Ok(solutions)
+}
+
+// the first ID of each read's name
+pub fn ids_by_name(maps : &Maps) -> HashMap<&str, usize> {
+    let mut ids = HashMap::new();
+    for id in (0..maps.num_ids()).rev() {
+        ids.insert(maps.get_name_for(id), id);
+    }
+    ids
+}
+
+pub fn id_of(ids : &HashMap<&str, usize>, name : &str) -> Result<usize, io::Error> {
+    ids.get(name).cloned().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData,
+        format!("The overlaps refer to read '{}', which is not in the input.", name)))
+}
+
+// a line of output, without the line break
+pub fn format_solution(s : &Solution, maps : &Maps) -> String {
+    let mut formatted = format!("{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                            maps.get_name_for(s.id_a),
+                            maps.get_name_for(s.id_b),
+                            s.orientation,
+                            s.overhang_left_a,
+                            s.overhang_right_b,
+                            s.overlap_a,
+                            s.overlap_b,
+                            s.errors,
+    );
+    if let (Some(score), Some(identity)) = (s.score, s.identity){
+        formatted.push_str(&format!("\t{}\t{:.4}", score, identity));
+    }
+    formatted
+}
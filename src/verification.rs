@@ -2,11 +2,12 @@ use bio::alignment::distance::{hamming, levenshtein};
 
 
 use std;
-use std::cmp::max;
+use std::cmp::{min, max};
+use std::mem::swap;
 use std::collections::HashSet;
 
 use structs::solutions::{Candidate, Solution};
-use structs::run_config::{Config, Maps};
+use structs::run_config::{Config, Maps, Scoring};
 use search;
 use useful::{relative_orientation, companion_id, for_reversed_string};
 
@@ -53,7 +54,7 @@ pub fn verify(id_a : usize, c : Candidate, config : &Config, maps : &Maps) -> Op
     //b3 is usize, so implicitly b3 >= 0
     let a_part : &[u8] = &maps.get_string(id_a)  [c.a1()..(c.a1()+c.a2())];
     let b_part : &[u8] = &maps.get_string(c.id_b)[c.b1()..(c.b1()+c.b2())];
-    let k_limit = (config.err_rate*(max(c.overlap_a, c.overlap_b) as f32)).floor() as u32;
+    let k_limit = config.max_errors_for(max(c.overlap_a, c.overlap_b)) as u32;
 
     let errors : u32 = if config.edit_distance{
         modified_levenshtein(a_part, b_part)
@@ -61,11 +62,124 @@ pub fn verify(id_a : usize, c : Candidate, config : &Config, maps : &Maps) -> Op
         assert!(a_part.len() == b_part.len());
         hamming(a_part, b_part) as u32
     };
-    if errors <= k_limit{
-        Some(solution_from_candidate(c, id_a, errors, maps, config))
-    }else{
-        None
+    if errors > k_limit{
+        //the filters only generate candidates within the error limit, so a scored overlap must be within it too
+        return None;
+    }
+    if let Some(ref scoring) = config.scoring {
+        //with weighted scoring, the score must also be within its own limit
+        let (score, identity) = match affine_score(a_part, b_part, scoring){
+            Some(x) => x,
+            None => return None,
+        };
+        let score_limit = (scoring.max_score_rate*(max(c.overlap_a, c.overlap_b) as f32)).floor() as u32;
+        if score <= score_limit{
+            let mut sol = solution_from_candidate(c, id_a, errors, maps, config);
+            sol.score = Some(score);
+            sol.identity = Some(identity);
+            return Some(sol);
+        }
+        return None;
     }
+    Some(solution_from_candidate(c, id_a, errors, maps, config))
+}
+
+#[derive(Clone, Copy)]
+struct AlignCell{
+    cost : u32,
+    matches : u32,
+    columns : u32,
+}
+
+impl AlignCell{
+    fn unreachable() -> AlignCell {
+        AlignCell{cost : std::u32::MAX, matches : 0, columns : 0}
+    }
+
+    #[inline]
+    fn step(self, cost : u32, is_match : bool) -> AlignCell {
+        AlignCell{
+            cost : self.cost.saturating_add(cost),
+            matches : self.matches + if is_match {1} else {0},
+            columns : self.columns + 1,
+        }
+    }
+
+    //lowest cost wins. ties are broken in favour of more matches
+    #[inline]
+    fn best(self, other : AlignCell) -> AlignCell {
+        if (other.cost, self.matches) < (self.cost, other.matches) {other} else {self}
+    }
+}
+
+/*
+Weighted counterpart of modified_levenshtein. Aligns the strings globally with
+substitution and affine gap costs (Gotoh). The first and last characters are forced to be
+substitutions as before. Returns the cost and the identity (matches per alignment column)
+of the cheapest alignment, or None if the distance is undefined.
+*/
+pub fn affine_score(a_part : &[u8], b_part : &[u8], scoring : &Scoring) -> Option<(u32, f32)> {
+    let identity = |cell : AlignCell| if cell.columns == 0 {1.0} else {cell.matches as f32 / cell.columns as f32};
+    if a_part.len() == b_part.len() && a_part.len() <= 2{
+        //no indels possible
+        let mut errs = 0;
+        if a_part.len() >= 1 {
+            errs += error_at_pos_in_both(a_part, b_part, true);
+        }
+        if a_part.len() >= 2 {
+            errs += error_at_pos_in_both(a_part, b_part, false);
+        }
+        let len = a_part.len() as u32;
+        let cell = AlignCell{cost : errs*scoring.substitution, matches : len - errs, columns : len};
+        return Some((cell.cost, identity(cell)));
+    }
+    if a_part.len() < 2 || b_part.len() < 2{
+        return None;
+    }
+    let a_inner = &a_part[1..a_part.len()-1];
+    let b_inner = &b_part[1..b_part.len()-1];
+    let open = scoring.gap_open + scoring.gap_extend;
+    let extend = scoring.gap_extend;
+
+    // m: ends in aligned pair. x: ends in a char against a gap. y: ends in b char against a gap
+    let mut m_row : Vec<AlignCell> = vec![AlignCell::unreachable(); b_inner.len()+1];
+    let mut x_row : Vec<AlignCell> = vec![AlignCell::unreachable(); b_inner.len()+1];
+    let mut y_row : Vec<AlignCell> = vec![AlignCell::unreachable(); b_inner.len()+1];
+    m_row[0] = AlignCell{cost : 0, matches : 0, columns : 0};
+    for j in 1..b_inner.len()+1{
+        y_row[j] = if j == 1 {m_row[0].step(open, false)} else {y_row[j-1].step(extend, false)};
+    }
+    for i in 1..a_inner.len()+1{
+        let mut m_next = vec![AlignCell::unreachable(); b_inner.len()+1];
+        let mut x_next = vec![AlignCell::unreachable(); b_inner.len()+1];
+        let mut y_next = vec![AlignCell::unreachable(); b_inner.len()+1];
+        x_next[0] = if i == 1 {m_row[0].step(open, false)} else {x_row[0].step(extend, false)};
+        for j in 1..b_inner.len()+1{
+            let (a, b) = (a_inner[i-1], b_inner[j-1]);
+            let is_match = a == b && a != search::READ_ERR;
+            let diag = m_row[j-1].best(x_row[j-1]).best(y_row[j-1]);
+            m_next[j] = diag.step(if is_match {0} else {scoring.substitution}, is_match);
+            x_next[j] = m_row[j].step(open, false)
+                .best(x_row[j].step(extend, false))
+                .best(y_row[j].step(open, false));
+            y_next[j] = m_next[j-1].step(open, false)
+                .best(y_next[j-1].step(extend, false))
+                .best(x_next[j-1].step(open, false));
+        }
+        m_row = m_next;
+        x_row = x_next;
+        y_row = y_next;
+    }
+    let last = b_inner.len();
+    let mut cell = m_row[last].best(x_row[last]).best(y_row[last]);
+    for &first in [true, false].iter(){
+        let err = error_at_pos_in_both(a_part, b_part, first);
+        cell = cell.step(err*scoring.substitution, err == 0);
+    }
+    if cell.cost == std::u32::MAX {
+        return None;
+    }
+    Some((cell.cost, identity(cell)))
 }
 
 
@@ -109,6 +223,75 @@ fn error_at_pos_in_both(a_part : &[u8], b_part : &[u8], first : bool) -> u32 {
     }
 }
 
+/*
+A cheapest alignment of the strings with unit edit costs (as levenshtein), given as its columns.
+Each column pairs indices into a and b. A symbol against a gap has None on the other side.
+Only alignments within 'band' diagonals of the main diagonal are considered, so the band should
+be at least the distance between the strings (i.e. the errors of a verified overlap). Cells outside
+are skipped, so the time and space taken are proportional to the length of the strings times the band.
+*/
+pub fn edit_alignment(a : &[u8], b : &[u8], band : usize) -> Vec<(Option<usize>, Option<usize>)> {
+    const DIAG : u8 = 0;
+    const UP : u8 = 1;   // a symbol of a against a gap
+    const LEFT : u8 = 2; // a symbol of b against a gap
+    let (n, m) = (a.len(), b.len());
+    let band = min(max(band, max(n, m) - min(n, m)), max(n, m));
+    let width = 2 * band + 1;
+    // cell (i, j) is at k = j + band - i of row i
+    let mut trace = vec![DIAG; (n + 1) * width];
+    let mut prev_row = vec![std::u32::MAX; width];
+    let mut row = vec![std::u32::MAX; width];
+    for i in 0..n+1 {
+        for cost in row.iter_mut() {
+            *cost = std::u32::MAX;
+        }
+        for j in i.saturating_sub(band)..min(m, i + band)+1 {
+            let k = j + band - i;
+            if i == 0 && j == 0 {
+                row[k] = 0;
+                continue;
+            }
+            let (mut best, mut op) = (std::u32::MAX, DIAG);
+            if i > 0 && j > 0 {
+                let is_match = a[i-1] == b[j-1] && a[i-1] != search::READ_ERR;
+                best = prev_row[k].saturating_add(if is_match {0} else {1});
+            }
+            if i > 0 && k + 1 < width && prev_row[k+1].saturating_add(1) < best {
+                best = prev_row[k+1] + 1;
+                op = UP;
+            }
+            if j > 0 && k > 0 && row[k-1].saturating_add(1) < best {
+                best = row[k-1] + 1;
+                op = LEFT;
+            }
+            row[k] = best;
+            trace[i * width + k] = op;
+        }
+        swap(&mut prev_row, &mut row);
+    }
+    let mut columns = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        match trace[i * width + j + band - i] {
+            DIAG => {
+                i -= 1;
+                j -= 1;
+                columns.push((Some(i), Some(j)));
+            },
+            UP => {
+                i -= 1;
+                columns.push((Some(i), None));
+            },
+            _ => {
+                j -= 1;
+                columns.push((None, Some(j)));
+            },
+        }
+    }
+    columns.reverse();
+    columns
+}
+
 /*
 Translates the input Candidate to a Solution.
 This function does NOT check whether the input candidate is for a real solution.
@@ -134,6 +317,8 @@ fn solution_from_candidate(c : Candidate, id_a : usize, errors : u32,
         overhang_left_a : c.overhang_left_a,
         overhang_right_b : (c.b3(b_len) as i32) - (c.a3(a_len) as i32),
         errors : errors,
+        score : None,
+        identity : None,
     };
     translate_solution_to_external(&mut sol, config, maps);
     sol